@@ -1,15 +1,49 @@
 /// Command line application to run an extended version of the well known
 /// FizzBuzz game.
 #[derive(Debug, clap::Parser)]
-#[command(author, version, about, long_about = None)]
+#[command(
+    author,
+    version,
+    about,
+    long_about = None,
+    subcommand_negates_reqs = true,
+    args_conflicts_with_subcommands = true
+)]
 pub struct Config {
     /// How many iterations of the FizzBuzz game to play .
-    #[arg(short)]
-    pub t: u32,
+    #[arg(short, required_unless_present = "at")]
+    pub t: Option<u32>,
     /// Multiples of `f` are going to print out `Fizz`.
-    #[arg(short)]
-    pub f: u32,
+    #[arg(short, required = true)]
+    pub f: Option<u32>,
     /// Multiples of `b` are going to print out `Buzz`.
-    #[arg(short)]
-    pub b: u32,
+    #[arg(short, required = true)]
+    pub b: Option<u32>,
+    /// Only prints the output of the game at iteration `N`, instead of playing it.
+    #[arg(long, value_name = "N", conflicts_with = "t")]
+    pub at: Option<u32>,
+    /// Stops the game with an error after `N` iterations.
+    #[arg(long, value_name = "N")]
+    pub max_iterations: Option<u64>,
+    /// Stops the game with an error, before its output exceeds `N` bytes.
+    #[arg(long, value_name = "N")]
+    pub max_bytes: Option<u64>,
+    /// Stops the game with an error at the first token, that is longer than `N` bytes.
+    #[arg(long, value_name = "N")]
+    pub max_token_len: Option<usize>,
+    /// Stops the game with an error after it ran for `N` seconds.
+    #[arg(long, value_name = "N")]
+    pub max_seconds: Option<u64>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum Command {
+    /// Checks the rules of a game for rules, that never produce a token, and for
+    /// iterations without a token.
+    Validate {
+        /// The rules of the game, like `3:Fizz,5:Buzz,15:FizzBuzz,numeric`.
+        spec: String,
+    },
 }
@@ -1,18 +1,59 @@
-use args::Config;
+use std::io::{self, BufWriter, Write};
+use std::process::ExitCode;
+
+use args::{Command, Config};
 use clap::Parser;
-use fizz_buzz::error::Result;
+use fizz_buzz::error::{FizzBuzzError, Result};
+use fizz_buzz::CancellationToken;
 
 use crate::traditional::play_traditional;
+use crate::validate::validate;
 
 mod args;
+mod progress;
 mod traditional;
+mod validate;
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(code) => code,
+        Err(err @ FizzBuzzError::Cancelled { .. }) => {
+            eprintln!("{err}");
+            ExitCode::from(130)
+        }
+        Err(err) => {
+            eprintln!("Error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
 
-fn main() -> Result<()> {
+fn run() -> Result<ExitCode> {
     let args = Config::parse();
 
+    if let Some(Command::Validate { spec }) = &args.command {
+        let valid = validate(spec, io::stdout().lock())?;
+        return Ok(if valid {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        });
+    }
+
     println!("Running FizzBuzz with following configuration:\n{args:#?}\n\n");
 
-    let output = play_traditional(args)?;
-    println!("{output}");
-    Ok(())
+    // Ctrl-C stops the game after the current iteration, keeping its output so far.
+    let cancellation = CancellationToken::new();
+    let cancel = cancellation.clone();
+    if let Err(err) = ctrlc::set_handler(move || cancel.cancel()) {
+        eprintln!("Could not handle Ctrl-C: {err}");
+    }
+
+    let mut out = BufWriter::new(io::stdout().lock());
+    let result = play_traditional(args, cancellation, &mut out);
+    // The output of a failed game is finished as well, but its error takes precedence.
+    let finished = writeln!(out).and_then(|()| out.flush());
+    result?;
+    finished?;
+    Ok(ExitCode::SUCCESS)
 }
@@ -0,0 +1,24 @@
+use std::io::{self, IsTerminal, Write};
+
+/// Runs with fewer iterations finish too quickly to need a progress bar.
+const MIN_ITERATIONS: u32 = 1_000_000;
+const WIDTH: u64 = 40;
+
+/// Returns the number of iterations between two updates of a progress bar for a
+/// game of `t` iterations, or `None` if no progress bar should be shown.
+pub fn progress_interval(t: u32) -> Option<u64> {
+    (t >= MIN_ITERATIONS && io::stderr().is_terminal()).then(|| u64::from(t) / 100)
+}
+
+/// Draws a progress bar for `i` of `t` iterations onto stderr.
+pub fn draw_progress(i: u64, t: u64) {
+    let filled = i * WIDTH / t.max(1);
+    let bar = format!(
+        "{}{}",
+        "#".repeat(filled as usize),
+        " ".repeat((WIDTH - filled) as usize)
+    );
+    let mut err = io::stderr().lock();
+    let _ = write!(err, "\r[{bar}] {:>3}% ({i}/{t})", i * 100 / t.max(1));
+    let _ = err.flush();
+}
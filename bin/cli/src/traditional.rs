@@ -1,19 +1,61 @@
+use std::io;
+use std::time::Duration;
+
 use crate::args::Config;
-use fizz_buzz::{
-    error::Result, ConsecutiveTokens, FormattingOptions, Numeric, Tokenizer, Traditional,
-};
+use crate::progress::{draw_progress, progress_interval};
+use fizz_buzz::{error::Result, CancellationToken, FormattingOptions, Limits, RuleSet};
 
-pub fn play_traditional(cfg: Config) -> Result<String> {
+/// Plays the game described by `cfg` and writes its output into `out`. Stops the
+/// game, once `cancellation` is cancelled.
+pub fn play_traditional(
+    cfg: Config,
+    cancellation: CancellationToken,
+    mut out: impl io::Write,
+) -> Result<()> {
     let options = FormattingOptions {
         separator: Some("\n".to_string()),
         case: None,
     };
-    let Config { t, f, b } = cfg;
-    let fall_back = Box::new(Numeric);
-    let fizz = Box::new(ConsecutiveTokens::new("Fizz", "+", 1, f, vec![b])?);
-    let buzz = Box::new(ConsecutiveTokens::new("Buzz", "+", 1, b, vec![f])?);
-    let fizz_buzz = Box::new(Traditional::new("FizzBuzz", 2, vec![f, b])?);
-
-    let tokenizer = Tokenizer::new(vec![fall_back, fizz, buzz, fizz_buzz]);
-    tokenizer.produce_output(t, options)
+    let Config {
+        t,
+        f,
+        b,
+        at,
+        max_iterations,
+        max_bytes,
+        max_token_len,
+        max_seconds,
+        ..
+    } = cfg;
+    let limits = Limits {
+        max_iterations,
+        max_output_bytes: max_bytes,
+        max_token_len,
+        time_limit: max_seconds.map(Duration::from_secs),
+    };
+    // required by the argument parser, unless a subcommand is given
+    let (f, b) = (f.unwrap_or_default(), b.unwrap_or_default());
+    let tokenizer = RuleSet::builder()
+        .fallback_numeric()
+        .consecutive(f, "Fizz", "+", [b])
+        .consecutive(b, "Buzz", "+", [f])
+        .divisible_by_all([f, b], "FizzBuzz")
+        .priority(2)
+        .build()?
+        .with_limits(limits)
+        .with_cancellation(cancellation);
+    if let Some(i) = at {
+        let token = tokenizer.token_at(i, &options)?.unwrap_or_default();
+        out.write_all(token.as_bytes())?;
+        return Ok(());
+    }
+    let t = t.unwrap_or_default();
+    let Some(interval) = progress_interval(t) else {
+        return tokenizer.write_to(t, &options, out);
+    };
+    let result = tokenizer
+        .with_progress(interval, draw_progress)
+        .write_to(t, &options, out);
+    eprintln!();
+    result
 }
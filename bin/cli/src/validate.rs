@@ -0,0 +1,17 @@
+use std::io;
+
+use fizz_buzz::{error::Result, Tokenizer};
+
+/// Writes the diagnostics of the rules described by `spec` into `out`.
+/// Returns `false`, if there are any.
+pub fn validate(spec: &str, mut out: impl io::Write) -> Result<bool> {
+    let tokenizer: Tokenizer = spec.parse()?;
+    let diagnostics = tokenizer.lint();
+    if diagnostics.is_empty() {
+        writeln!(out, "No issues found in `{tokenizer}`.")?;
+    }
+    for diagnostic in &diagnostics {
+        writeln!(out, "warning: {diagnostic}")?;
+    }
+    Ok(diagnostics.is_empty())
+}
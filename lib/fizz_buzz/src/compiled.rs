@@ -0,0 +1,270 @@
+//! Compilation of periodic rule sets into a lookup table.
+//!
+//! If the conditions of all rules are periodic, see [`crate::TokenCondition::period`], the
+//! winners of an iteration only depend on its residue modulo the least common
+//! multiple of all periods. The winners of every residue are resolved once in
+//! advance, so that a run only has to evaluate the winning rules of each iteration.
+
+use std::fmt;
+use std::io;
+use std::ops::Range;
+
+use crate::context::{EvaluationContext, History, RuleState};
+use crate::error::Result;
+use crate::formatting::FormattingOptions;
+use crate::integer::{self, Integer};
+use crate::range::IterationRange;
+use crate::resolution::ResolutionStrategy;
+use crate::tokenizer::{Tokenizer, Tokens};
+
+/// The biggest period, that is compiled into a lookup table.
+const MAX_PERIOD: u64 = 1 << 20;
+
+/// A [`Tokenizer`], whose periodic rules were compiled into a lookup table.
+/// Created by [`Tokenizer::compile`].
+///
+/// Produces the same output as the [`Tokenizer`] it was compiled from.
+/// If the rules could not be compiled, it falls back to evaluating every
+/// rule at every iteration.
+pub struct CompiledTokenizer<N: Integer = u32> {
+    tokenizer: Tokenizer<N>,
+    wheel: Option<Wheel<N>>,
+}
+
+impl<N: Integer> Tokenizer<N> {
+    /// Precomputes the winners of every residue within one period of the rules.
+    ///
+    /// The rules are only compiled, if every rule reports a [`crate::TokenCondition::period`],
+    /// no rule is [stateful](crate::TokenCondition::is_stateful), the [`ResolutionStrategy`]
+    /// is not [custom](ResolutionStrategy::Custom) and the least common multiple of
+    /// all periods does not exceed 2^20. Otherwise the returned [`CompiledTokenizer`]
+    /// evaluates the rules like the [`Tokenizer`] does.
+    pub fn compile(self) -> CompiledTokenizer<N> {
+        let wheel = Wheel::compile(&self);
+        CompiledTokenizer {
+            tokenizer: self,
+            wheel,
+        }
+    }
+}
+
+impl<N: Integer> CompiledTokenizer<N> {
+    /// The length of the lookup table, if the rules were compiled.
+    pub fn period(&self) -> Option<&N> {
+        self.wheel.as_ref().map(|w| &w.period)
+    }
+
+    /// Returns the [`Tokenizer`], that was compiled.
+    pub fn into_inner(self) -> Tokenizer<N> {
+        self.tokenizer
+    }
+
+    /// See [`Tokenizer::iter`].
+    pub fn iter<'a>(
+        &'a self,
+        range: impl Into<IterationRange<N>>,
+        options: &'a FormattingOptions,
+    ) -> Tokens<'a, N> {
+        let range = range.into();
+        self.tokens(&range, options)
+    }
+
+    /// See [`Tokenizer::produce_output`].
+    pub fn produce_output(
+        &self,
+        range: impl Into<IterationRange<N>>,
+        options: FormattingOptions,
+    ) -> Result<String> {
+        let mut output = String::new();
+        match self.write_to_fmt(range, &options, &mut output) {
+            Ok(()) => Ok(output),
+            Err(err) => Err(err.with_output(output)),
+        }
+    }
+
+    /// See [`Tokenizer::write_to`].
+    pub fn write_to(
+        &self,
+        range: impl Into<IterationRange<N>>,
+        options: &FormattingOptions,
+        writer: impl io::Write,
+    ) -> Result<()> {
+        let range = range.into();
+        self.tokens(&range, options).write_io(writer)
+    }
+
+    /// See [`Tokenizer::write_to_fmt`].
+    pub fn write_to_fmt(
+        &self,
+        range: impl Into<IterationRange<N>>,
+        options: &FormattingOptions,
+        mut writer: impl fmt::Write,
+    ) -> Result<()> {
+        let range = range.into();
+        self.tokens(&range, options).write_joined(&mut writer)?;
+        Ok(())
+    }
+
+    fn tokens<'a>(
+        &'a self,
+        range: &IterationRange<N>,
+        options: &'a FormattingOptions,
+    ) -> Tokens<'a, N> {
+        Tokens::new(&self.tokenizer, options, range, 0, range.len())
+            .with_wheel(self.wheel.as_ref())
+            .controlled()
+    }
+}
+
+/// The winners of every residue within one period of a rule set.
+pub(crate) struct Wheel<N: Integer> {
+    period: N,
+    /// The winners of every residue, as a range of `winners`.
+    /// `None` for residues, whose resolution failed, as the error has to be
+    /// reported for the actual iteration.
+    residues: Vec<Option<Range<usize>>>,
+    winners: Vec<usize>,
+}
+
+impl<N: Integer> Wheel<N> {
+    fn compile(tokenizer: &Tokenizer<N>) -> Option<Self> {
+        if tokenizer.is_stateful()
+            || matches!(tokenizer.resolution(), ResolutionStrategy::Custom(_))
+        {
+            return None;
+        }
+        let rules = tokenizer.rules();
+        let period = rules.iter().try_fold(N::one(), |period, rule| {
+            let p = rule.period().filter(|p| *p > N::zero())?;
+            integer::lcm(&period, &p)
+        })?;
+        let len = period.to_u64().filter(|len| *len <= MAX_PERIOD)?;
+
+        // Stateless rules neither look at the history nor at their state.
+        let history = History::new(0);
+        let mut states: Vec<_> = rules.iter().map(|_| RuleState::default()).collect();
+        let mut wheel = Self {
+            period,
+            residues: Vec::with_capacity(len as usize),
+            winners: Vec::new(),
+        };
+        let mut matches = Vec::new();
+        let mut winners = Vec::new();
+        let mut residue = N::zero();
+        for _ in 0..len {
+            matches.clear();
+            for (n, rule) in rules.iter().enumerate() {
+                let mut ctx = EvaluationContext::new(&history, &mut states[n], n);
+                if rule.evaluate_in(residue.clone(), &mut ctx).is_some() {
+                    matches.push(n);
+                }
+            }
+            let resolved = tokenizer.resolve(&residue, &matches, &mut winners).ok();
+            wheel.residues.push(resolved.map(|()| {
+                let start = wheel.winners.len();
+                wheel.winners.extend_from_slice(&winners);
+                start..wheel.winners.len()
+            }));
+            residue = residue + N::one();
+        }
+        Some(wheel)
+    }
+
+    /// The winners of iteration `i`, or `None` if they have to be resolved at `i`.
+    pub(crate) fn lookup(&self, i: &N) -> Option<&[usize]> {
+        let mut residue = i.clone() % self.period.clone();
+        if residue.is_negative() {
+            residue = residue + self.period.clone();
+        }
+        let index = usize::try_from(residue.to_u64()?).ok()?;
+        let winners = self.residues.get(index)?.clone()?;
+        Some(&self.winners[winners])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::{fizz_buzz_rules, space_separated};
+    use crate::{
+        error::FizzBuzzError, ConsecutiveTokens, Direction, IterationRange, Numeric, TieBreak,
+        TokenCondition, Tokenizer, Traditional,
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_compiled_output() {
+        let compiled = Tokenizer::new(fizz_buzz_rules::<u32>()).compile();
+        assert_eq!(compiled.period(), Some(&15));
+
+        let tokenizer = Tokenizer::new(fizz_buzz_rules::<u32>());
+        let ranges = [
+            IterationRange::from(1000),
+            IterationRange::new(1000, 1, true, 7, Direction::Descending).unwrap(),
+            IterationRange::new(u32::MAX - 100, u32::MAX, true, 1, Direction::Ascending).unwrap(),
+        ];
+        for range in ranges {
+            let expected = tokenizer
+                .produce_output(range.clone(), space_separated())
+                .unwrap();
+            let output = compiled.produce_output(range, space_separated()).unwrap();
+            assert_eq!(output, expected);
+        }
+
+        // Negative iterations are mapped onto the residues of the period.
+        let compiled = Tokenizer::new(fizz_buzz_rules::<i16>()).compile();
+        let tokenizer = Tokenizer::new(fizz_buzz_rules::<i16>());
+        let range = IterationRange::new(-50i16, 50, true, 1, Direction::Ascending).unwrap();
+        let expected = tokenizer
+            .produce_output(range.clone(), space_separated())
+            .unwrap();
+        assert_eq!(
+            compiled.produce_output(range, space_separated()).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_compile_fallback() {
+        // -------------------------   Non-periodic rule    ------------------------- //
+        let mut rules = fizz_buzz_rules::<u32>();
+        rules.push(Box::new(
+            ConsecutiveTokens::new("Fizz", "+", 1, 3, vec![5]).unwrap(),
+        ));
+        let compiled = Tokenizer::new(rules).compile();
+        assert_eq!(compiled.period(), None);
+        let output = compiled.produce_output(9, space_separated()).unwrap();
+        assert_eq!(output, "1 2 Fizz 4 Buzz Fizz 7 8 Fizz+");
+
+        // -------------------------   Period too large     ------------------------- //
+        let rules: Vec<Box<dyn TokenCondition<u64>>> = vec![
+            Box::new(Numeric),
+            Box::new(Traditional::new("Big", 1, vec![1_000_003, 1_000_033]).unwrap()),
+        ];
+        let compiled = Tokenizer::new(rules).compile();
+        assert_eq!(compiled.period(), None);
+        let output = compiled.produce_output(3, space_separated()).unwrap();
+        assert_eq!(output, "1 2 3");
+
+        // -------------------------   Ambiguous residues   ------------------------- //
+        let rules: Vec<Box<dyn TokenCondition>> = vec![
+            Box::new(Numeric),
+            Box::new(Traditional::new("Fizz", 1, vec![2]).unwrap()),
+            Box::new(Traditional::new("Buzz", 1, vec![3]).unwrap()),
+        ];
+        let compiled = Tokenizer::new(rules)
+            .with_tie_break(TieBreak::Error)
+            .compile();
+        assert_eq!(compiled.period(), Some(&6));
+        assert_eq!(
+            compiled.produce_output(5, space_separated()).unwrap(),
+            "1 Fizz Buzz Fizz 5"
+        );
+        let err = compiled.produce_output(6, space_separated()).unwrap_err();
+        let expected = FizzBuzzError::AmbiguousRules {
+            first: "Fizz".to_string(),
+            second: "Buzz".to_string(),
+            iteration: "6".to_string(),
+        };
+        assert_eq!(err, expected);
+    }
+}
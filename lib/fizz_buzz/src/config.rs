@@ -0,0 +1,522 @@
+//! Serializable descriptions of games, that are loaded from configuration files.
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed, MapAccess, Unexpected, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::error::{FizzBuzzError, Result};
+use crate::formatting::FormattingOptions;
+use crate::integer::Integer;
+use crate::rule_set::RuleSet;
+use crate::tokenizer::Tokenizer;
+
+/// Describes the preset rules and the [`FormattingOptions`] of a
+/// game and can be read from TOML, JSON or YAML documents:
+///
+/// ```toml
+/// [formatting]
+/// separator = " "
+/// case = "upper"
+///
+/// [[rules]]
+/// type = "numeric"
+///
+/// [[rules]]
+/// type = "consecutive"
+/// token = "Fizz"
+/// suffix = "+"
+/// divisor = 3
+/// rivals = [5]
+///
+/// [[rules]]
+/// type = "traditional"
+/// token = "FizzBuzz"
+/// divisors = [3, 5]
+/// ```
+///
+/// Rules without a `priority` are ranked by the divisors they imply, see
+/// [`RuleSetBuilder::auto_priority`](crate::RuleSetBuilder::auto_priority).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    deny_unknown_fields,
+    bound(deserialize = "N: Integer + Deserialize<'de>")
+)]
+pub struct GameConfig<N = u32> {
+    pub rules: Vec<RuleConfig<N>>,
+    #[serde(default)]
+    pub formatting: FormattingOptions,
+}
+
+/// The description of one of the preset rules. Its `type` selects the preset.
+///
+/// Unknown fields, fields of other presets and zero divisors are rejected
+/// while reading the document, at the location of the offending field.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleConfig<N = u32> {
+    /// See [`crate::Traditional`].
+    Traditional {
+        token: String,
+        divisors: Vec<N>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        priority: Option<u32>,
+    },
+    /// See [`crate::ConsecutiveTokens`]. The `rivals` are optional.
+    Consecutive {
+        token: String,
+        suffix: String,
+        divisor: N,
+        rivals: Vec<N>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        priority: Option<u32>,
+    },
+    /// See [`crate::Numeric`].
+    Numeric,
+}
+
+/// The fields of all [`RuleConfig`]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(field_identifier, rename_all = "snake_case")]
+enum Field {
+    Type,
+    Token,
+    Suffix,
+    Divisor,
+    Divisors,
+    Rivals,
+    Priority,
+}
+
+impl Field {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Type => "type",
+            Self::Token => "token",
+            Self::Suffix => "suffix",
+            Self::Divisor => "divisor",
+            Self::Divisors => "divisors",
+            Self::Rivals => "rivals",
+            Self::Priority => "priority",
+        }
+    }
+}
+
+/// The `type` of a [`RuleConfig`].
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum RuleType {
+    Traditional,
+    Consecutive,
+    Numeric,
+}
+
+impl RuleType {
+    fn fields(self) -> &'static [&'static str] {
+        match self {
+            Self::Traditional => &["type", "token", "divisors", "priority"],
+            Self::Consecutive => &["type", "token", "suffix", "divisor", "rivals", "priority"],
+            Self::Numeric => &["type"],
+        }
+    }
+}
+
+/// Reads the key of a field of a rule of the `kind`, if it is known already, and
+/// rejects fields, that were `seen` before or do not belong to the rule. As the
+/// key is still being read, the error points to it.
+struct FieldSeed<'a> {
+    kind: Option<RuleType>,
+    seen: &'a [Field],
+}
+
+impl<'de> DeserializeSeed<'de> for FieldSeed<'_> {
+    type Value = Field;
+
+    fn deserialize<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<Field, D::Error> {
+        let field = Field::deserialize(deserializer)?;
+        if self.seen.contains(&field) {
+            return Err(de::Error::duplicate_field(field.name()));
+        }
+        match self.kind {
+            Some(kind) if !kind.fields().contains(&field.name()) => {
+                Err(de::Error::unknown_field(field.name(), kind.fields()))
+            }
+            _ => Ok(field),
+        }
+    }
+}
+
+/// A divisor, that is rejected while it is read, if it is zero.
+struct Divisor<N>(N);
+
+impl<'de, N: Integer + Deserialize<'de>> Deserialize<'de> for Divisor<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let divisor = N::deserialize(deserializer)?;
+        if divisor.is_zero() {
+            return Err(de::Error::invalid_value(
+                Unexpected::Unsigned(0),
+                &"a divisor other than zero",
+            ));
+        }
+        Ok(Self(divisor))
+    }
+}
+
+fn divisors<N>(divisors: Vec<Divisor<N>>) -> Vec<N> {
+    divisors.into_iter().map(|d| d.0).collect()
+}
+
+// Rules are read field by field, instead of being buffered to look up their `type`
+// first, as buffering loses the locations of the fields.
+impl<'de, N: Integer + Deserialize<'de>> Deserialize<'de> for RuleConfig<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_map(RuleVisitor(PhantomData))
+    }
+}
+
+struct RuleVisitor<N>(PhantomData<N>);
+
+impl<'de, N: Integer + Deserialize<'de>> Visitor<'de> for RuleVisitor<N> {
+    type Value = RuleConfig<N>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a rule with a `type`")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(
+        self,
+        mut map: A,
+    ) -> std::result::Result<RuleConfig<N>, A::Error> {
+        let mut kind = None;
+        let mut seen = Vec::new();
+        let (mut token, mut suffix, mut priority) = (None, None, None);
+        let (mut divisor, mut divisors, mut rivals) = (None, None, None);
+        while let Some(field) = map.next_key_seed(FieldSeed { kind, seen: &seen })? {
+            seen.push(field);
+            match field {
+                Field::Type => kind = Some(map.next_value::<RuleType>()?),
+                Field::Token => token = Some(map.next_value::<String>()?),
+                Field::Suffix => suffix = Some(map.next_value::<String>()?),
+                Field::Divisor => divisor = Some(map.next_value::<Divisor<N>>()?.0),
+                Field::Divisors => divisors = Some(map.next_value::<Vec<Divisor<N>>>()?),
+                Field::Rivals => rivals = Some(map.next_value::<Vec<Divisor<N>>>()?),
+                Field::Priority => priority = Some(map.next_value::<u32>()?),
+            }
+        }
+
+        let kind = kind.ok_or_else(|| de::Error::missing_field("type"))?;
+        // Fields in front of the `type` could not be checked while they were read.
+        if let Some(field) = seen.iter().find(|f| !kind.fields().contains(&f.name())) {
+            return Err(de::Error::unknown_field(field.name(), kind.fields()));
+        }
+        let token = || token.ok_or_else(|| de::Error::missing_field("token"));
+        Ok(match kind {
+            RuleType::Traditional => RuleConfig::Traditional {
+                token: token()?,
+                divisors: divisors
+                    .map(self::divisors)
+                    .ok_or_else(|| de::Error::missing_field("divisors"))?,
+                priority,
+            },
+            RuleType::Consecutive => RuleConfig::Consecutive {
+                token: token()?,
+                suffix: suffix.ok_or_else(|| de::Error::missing_field("suffix"))?,
+                divisor: divisor.ok_or_else(|| de::Error::missing_field("divisor"))?,
+                rivals: rivals.map(self::divisors).unwrap_or_default(),
+                priority,
+            },
+            RuleType::Numeric => RuleConfig::Numeric,
+        })
+    }
+}
+
+impl<N: Integer + DeserializeOwned> GameConfig<N> {
+    /// Reads a game from a TOML document.
+    pub fn from_toml(document: &str) -> Result<Self> {
+        toml::from_str(document).map_err(|err| {
+            let (line, column) = match err.span() {
+                Some(span) => line_and_column(document, span.start),
+                None => (None, None),
+            };
+            FizzBuzzError::Config {
+                format: "TOML",
+                line,
+                column,
+                message: err.message().to_string(),
+            }
+        })
+    }
+
+    /// Reads a game from a JSON document.
+    pub fn from_json(document: &str) -> Result<Self> {
+        serde_json::from_str(document).map_err(|err| {
+            let (line, column) = (err.line(), err.column());
+            config_error("JSON", Some(line), Some(column), err.to_string())
+        })
+    }
+
+    /// Reads a game from a YAML document.
+    pub fn from_yaml(document: &str) -> Result<Self> {
+        serde_yaml::from_str(document).map_err(|err| {
+            let location = err.location();
+            let line = location.as_ref().map(|l| l.line());
+            let column = location.as_ref().map(|l| l.column());
+            config_error("YAML", line, column, err.to_string())
+        })
+    }
+}
+
+impl<N: Integer> GameConfig<N> {
+    /// Builds a [`Tokenizer`] from the rules of the game. Use it together with
+    /// the [`GameConfig::formatting`] options.
+    ///
+    /// # Errors
+    /// Returns [`FizzBuzzError::InvalidRules`], if some of the rules are invalid,
+    /// i.e. because of a zero divisor.
+    pub fn tokenizer(&self) -> Result<Tokenizer<N>> {
+        let mut builder = RuleSet::builder().auto_priority();
+        for rule in &self.rules {
+            builder = match rule {
+                RuleConfig::Traditional {
+                    token,
+                    divisors,
+                    priority,
+                } => {
+                    let builder = builder.divisible_by_all(divisors.iter().cloned(), token.clone());
+                    match priority {
+                        Some(priority) => builder.priority(*priority),
+                        None => builder,
+                    }
+                }
+                RuleConfig::Consecutive {
+                    token,
+                    suffix,
+                    divisor,
+                    rivals,
+                    priority,
+                } => {
+                    let builder = builder.consecutive(
+                        divisor.clone(),
+                        token.clone(),
+                        suffix.clone(),
+                        rivals.iter().cloned(),
+                    );
+                    match priority {
+                        Some(priority) => builder.priority(*priority),
+                        None => builder,
+                    }
+                }
+                RuleConfig::Numeric => builder.fallback_numeric(),
+            };
+        }
+        builder.build()
+    }
+}
+
+/// Creates a [`FizzBuzzError::Config`] from a message, that ends with its location.
+fn config_error(
+    format: &'static str,
+    line: Option<usize>,
+    column: Option<usize>,
+    message: String,
+) -> FizzBuzzError {
+    let location = match (line, column) {
+        (Some(line), Some(column)) => format!(" at line {line} column {column}"),
+        _ => String::new(),
+    };
+    let message = message.strip_suffix(&location).unwrap_or(&message);
+    FizzBuzzError::Config {
+        format,
+        line,
+        column,
+        message: message.to_string(),
+    }
+}
+
+/// The line and column of the byte `offset` within `document`, counting from 1.
+fn line_and_column(document: &str, offset: usize) -> (Option<usize>, Option<usize>) {
+    let before = document.get(..offset).unwrap_or(document);
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .count()
+        + 1;
+    (Some(line), Some(column))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{GameConfig, RuleConfig};
+    use crate::error::{FizzBuzzError, RuleError};
+    use crate::{Case, FormattingOptions};
+    use pretty_assertions::assert_eq;
+
+    const TOML: &str = r#"
+[formatting]
+separator = " "
+case = "upper"
+
+[[rules]]
+type = "numeric"
+
+[[rules]]
+type = "consecutive"
+token = "Fizz"
+suffix = "+"
+divisor = 3
+rivals = [5]
+
+[[rules]]
+type = "consecutive"
+token = "Buzz"
+suffix = "+"
+divisor = 5
+rivals = [3]
+
+[[rules]]
+type = "traditional"
+token = "FizzBuzz"
+divisors = [3, 5]
+priority = 2
+"#;
+
+    fn expected() -> GameConfig {
+        let consecutive = |token: &str, divisor, rival| RuleConfig::Consecutive {
+            token: token.to_string(),
+            suffix: "+".to_string(),
+            divisor,
+            rivals: vec![rival],
+            priority: None,
+        };
+        GameConfig {
+            rules: vec![
+                RuleConfig::Numeric,
+                consecutive("Fizz", 3, 5),
+                consecutive("Buzz", 5, 3),
+                RuleConfig::Traditional {
+                    token: "FizzBuzz".to_string(),
+                    divisors: vec![3, 5],
+                    priority: Some(2),
+                },
+            ],
+            formatting: FormattingOptions {
+                separator: Some(" ".to_string()),
+                case: Some(Case::Upper),
+            },
+        }
+    }
+
+    #[test]
+    fn test_load_config() {
+        let config = GameConfig::from_toml(TOML).unwrap();
+        assert_eq!(config, expected());
+        let output = config
+            .tokenizer()
+            .unwrap()
+            .produce_output(15, config.formatting.clone())
+            .unwrap();
+        assert_eq!(
+            output,
+            "1 2 FIZZ 4 BUZZ FIZZ 7 8 FIZZ+ BUZZ 11 FIZZ 13 14 FIZZBUZZ"
+        );
+
+        // The other formats describe the same game.
+        let json = serde_json::to_string(&expected()).unwrap();
+        assert_eq!(GameConfig::from_json(&json).unwrap(), expected());
+        let yaml = serde_yaml::to_string(&expected()).unwrap();
+        assert_eq!(GameConfig::from_yaml(&yaml).unwrap(), expected());
+    }
+
+    #[test]
+    fn test_config_errors() {
+        // Unknown fields are reported with their location.
+        let err = GameConfig::<u32>::from_toml(&TOML.replace("rivals", "rival")).unwrap_err();
+        let FizzBuzzError::Config {
+            format,
+            line,
+            column,
+            message,
+        } = err
+        else {
+            panic!("unexpected error {err:?}");
+        };
+        assert_eq!((format, line, column), ("TOML", Some(14), Some(1)));
+        assert!(message.contains("unknown field `rival`"), "{message}");
+
+        // Zero divisors are reported at the divisor.
+        let err = GameConfig::<u32>::from_toml(&TOML.replace("divisor = 5", "divisor = 0"));
+        let Err(FizzBuzzError::Config { line, message, .. }) = err else {
+            panic!("unexpected result {err:?}");
+        };
+        assert_eq!(line, Some(20));
+        assert!(
+            message.contains("expected a divisor other than zero"),
+            "{message}"
+        );
+
+        let json = r#"{"rules": [{"type": "numeric"}], "formating": {}}"#;
+        let err = GameConfig::<u32>::from_json(json).unwrap_err();
+        let expected = FizzBuzzError::Config {
+            format: "JSON",
+            line: Some(1),
+            column: Some(44),
+            message: "unknown field `formating`, expected `rules` or `formatting`".to_string(),
+        };
+        assert_eq!(err, expected);
+
+        let yaml = "rules:\n  - type: traditional\n    token: Fizz\n    divisors: [-3]\n";
+        let err = GameConfig::<u32>::from_yaml(yaml).unwrap_err();
+        let expected = FizzBuzzError::Config {
+            format: "YAML",
+            line: Some(4),
+            column: Some(16),
+            message: "rules[0].divisors[0]: invalid type: integer `-3`, expected u32".to_string(),
+        };
+        assert_eq!(err, expected);
+
+        let yaml = "rules:\n  - type: numeric\n  - type: traditional\n    token: Fizz\n    divisors: [0]\n";
+        let err = GameConfig::<u32>::from_yaml(yaml).unwrap_err();
+        let expected = FizzBuzzError::Config {
+            format: "YAML",
+            line: Some(5),
+            column: Some(15),
+            message:
+                "rules[1].divisors: invalid value: integer `0`, expected a divisor other than zero"
+                    .to_string(),
+        };
+        assert_eq!(err, expected);
+
+        // Invalid divisors of configurations, that were not loaded, are reported
+        // for the rule they belong to.
+        let config = GameConfig {
+            rules: vec![
+                RuleConfig::Numeric,
+                RuleConfig::Traditional {
+                    token: "Fizz".to_string(),
+                    divisors: vec![0],
+                    priority: None,
+                },
+            ],
+            formatting: FormattingOptions::default(),
+        };
+        let err = config.tokenizer().err().unwrap();
+        let expected = FizzBuzzError::InvalidRules {
+            errors: vec![RuleError {
+                index: 1,
+                name: "Fizz".to_string(),
+                error: FizzBuzzError::ZeroDivisor {
+                    rule: "Fizz".to_string(),
+                    position: 0,
+                },
+            }],
+        };
+        assert_eq!(err, expected);
+    }
+}
@@ -0,0 +1,201 @@
+//! Information about prior iterations of a run, that is made available to
+//! the rules of the game through an [`EvaluationContext`].
+
+use std::any::Any;
+use std::collections::VecDeque;
+
+use crate::integer::Integer;
+
+/// The outcome of a single iteration of a run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record<N: Integer = u32> {
+    value: N,
+    winners: Vec<usize>,
+    token: Option<String>,
+}
+
+impl<N: Integer> Record<N> {
+    /// The iteration value.
+    pub fn value(&self) -> &N {
+        &self.value
+    }
+
+    /// The indices of the rules that produced the token of the iteration, in the
+    /// order in which they were passed to the [`crate::Tokenizer`].
+    /// Empty, if the iteration did not produce a token.
+    pub fn winners(&self) -> &[usize] {
+        &self.winners
+    }
+
+    /// The token of the iteration before formatting was applied.
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+}
+
+/// The most recent iterations of a run.
+///
+/// Only as many iterations are kept as the rule with the biggest
+/// [`crate::TokenCondition::history_depth`] requests.
+#[derive(Debug, Clone)]
+pub struct History<N: Integer = u32> {
+    records: VecDeque<Record<N>>,
+    capacity: usize,
+}
+
+impl<N: Integer> History<N> {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            records: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Returns the record of the iteration `back` iterations in the past.
+    /// `get(0)` is the most recently recorded iteration.
+    pub fn get(&self, back: usize) -> Option<&Record<N>> {
+        self.records.get(back)
+    }
+
+    /// The most recently recorded iteration.
+    pub fn last(&self) -> Option<&Record<N>> {
+        self.get(0)
+    }
+
+    /// Iterates over the recorded iterations, starting with the most recent one.
+    pub fn iter(&self) -> impl Iterator<Item = &Record<N>> {
+        self.records.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Records the outcome of an iteration, forgetting the oldest record if
+    /// the capacity is exceeded. Buffers of forgotten records are reused.
+    pub(crate) fn record(&mut self, value: N, winners: &[usize], token: Option<&str>) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut record = if self.records.len() == self.capacity {
+            self.records.pop_back().expect("capacity is not zero")
+        } else {
+            Record {
+                value: value.clone(),
+                winners: Vec::new(),
+                token: None,
+            }
+        };
+        record.value = value;
+        record.winners.clear();
+        record.winners.extend_from_slice(winners);
+        match (token, &mut record.token) {
+            (Some(token), Some(buffer)) => {
+                buffer.clear();
+                buffer.push_str(token);
+            }
+            (token, buffer) => *buffer = token.map(str::to_string),
+        }
+        self.records.push_front(record);
+    }
+}
+
+/// Storage for arbitrary state of a single rule, that lives as long as a run.
+#[derive(Default)]
+pub(crate) struct RuleState(Option<Box<dyn Any + Send>>);
+
+/// Gives a rule access to prior iterations of the current run and to its own
+/// state, see [`crate::TokenCondition::evaluate_in`].
+pub struct EvaluationContext<'a, N: Integer = u32> {
+    history: &'a History<N>,
+    state: &'a mut RuleState,
+    rule: usize,
+}
+
+impl<'a, N: Integer> EvaluationContext<'a, N> {
+    pub(crate) fn new(history: &'a History<N>, state: &'a mut RuleState, rule: usize) -> Self {
+        Self {
+            history,
+            state,
+            rule,
+        }
+    }
+
+    /// The most recent iterations of the run.
+    pub fn history(&self) -> &History<N> {
+        self.history
+    }
+
+    /// The index of the evaluated rule, in the order in which the rules were
+    /// passed to the [`crate::Tokenizer`]. Can be used to find the iterations
+    /// won by the rule within the [`History`].
+    pub fn rule_index(&self) -> usize {
+        self.rule
+    }
+
+    /// The state of the evaluated rule, if it was set before and is of type `T`.
+    pub fn state<T: Any + Send>(&self) -> Option<&T> {
+        self.state.0.as_ref()?.downcast_ref()
+    }
+
+    /// The state of the evaluated rule. The state is initialized with its default
+    /// value on first access, or if the stored state is not of type `T`.
+    /// Rules using it have to be [stateful](crate::TokenCondition::is_stateful).
+    pub fn state_mut<T: Any + Send + Default>(&mut self) -> &mut T {
+        let state = &mut self.state.0;
+        if !state.as_ref().is_some_and(|s| s.is::<T>()) {
+            *state = Some(Box::new(T::default()));
+        }
+        state
+            .as_mut()
+            .and_then(|s| s.downcast_mut())
+            .expect("state was initialized with type `T`")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{EvaluationContext, History, RuleState};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_history() {
+        let mut history = History::new(2);
+        history.record(1u32, &[], None);
+        history.record(2, &[0], Some("Fizz"));
+        history.record(3, &[1, 2], Some("FizzBuzz"));
+
+        assert_eq!(history.len(), 2);
+        let last = history.last().unwrap();
+        assert_eq!(
+            (last.value(), last.winners(), last.token()),
+            (&3, &[1, 2][..], Some("FizzBuzz"))
+        );
+        let values: Vec<_> = history.iter().map(|r| *r.value()).collect();
+        assert_eq!(values, vec![3, 2]);
+
+        let mut disabled = History::new(0);
+        disabled.record(1u32, &[0], Some("Fizz"));
+        assert!(disabled.is_empty());
+    }
+
+    #[test]
+    fn test_rule_state() {
+        let history = History::<u32>::new(0);
+        let mut state = RuleState::default();
+        let mut ctx = EvaluationContext::new(&history, &mut state, 0);
+
+        assert_eq!(ctx.state::<usize>(), None);
+        *ctx.state_mut::<usize>() += 2;
+        *ctx.state_mut::<usize>() += 1;
+        assert_eq!(ctx.state::<usize>(), Some(&3));
+
+        // Accessing the state with another type resets it.
+        assert_eq!(ctx.state_mut::<String>(), "");
+        assert_eq!(ctx.state::<usize>(), None);
+    }
+}
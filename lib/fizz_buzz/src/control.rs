@@ -0,0 +1,123 @@
+//! Progress reporting and cooperative cancellation of a run, see
+//! [`Tokenizer::with_progress`](crate::Tokenizer::with_progress) and
+//! [`Tokenizer::with_cancellation`](crate::Tokenizer::with_cancellation).
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Requests a run to stop, from the same or another thread.
+///
+/// Clones share their state, so a clone can be kept to cancel a run of the
+/// [`Tokenizer`](crate::Tokenizer), the token was passed to. Runs check the token
+/// before every iteration and stop with [`FizzBuzzError::Cancelled`](crate::error::FizzBuzzError::Cancelled).
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stops all runs, that check this token. Can not be undone.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Called with the number of evaluated iterations and the total number of iterations of a run.
+type Observer = Box<dyn Fn(u64, u64) + Send + Sync>;
+
+/// The progress observer and the cancellation token of a [`Tokenizer`](crate::Tokenizer).
+#[derive(Default)]
+pub(crate) struct Monitor {
+    /// The observer and the number of iterations between two of its calls.
+    observer: Option<(u64, Observer)>,
+    cancellation: Option<CancellationToken>,
+}
+
+impl Monitor {
+    pub(crate) fn set_observer(&mut self, interval: u64, observer: Observer) {
+        self.observer = Some((interval.max(1), observer));
+    }
+
+    pub(crate) fn set_cancellation(&mut self, token: CancellationToken) {
+        self.cancellation = Some(token);
+    }
+
+    /// Whether a run has to report its progress or check for cancellation.
+    #[cfg(feature = "parallel")]
+    pub(crate) fn is_active(&self) -> bool {
+        self.observer.is_some() || self.cancellation.is_some()
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+
+    /// Reports the progress of a run, that evaluated `position` of `total` iterations,
+    /// whenever `position` is a multiple of the interval of the observer.
+    pub(crate) fn observe(&self, position: u64, total: u64) {
+        if let Some((interval, observer)) = &self.observer {
+            if position.is_multiple_of(*interval) {
+                observer(position, total);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::CancellationToken;
+    use crate::error::FizzBuzzError;
+    use crate::limits::Progress;
+    use crate::test::space_separated;
+    use crate::{FormattingOptions, Tokenizer};
+    use pretty_assertions::assert_eq;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_progress() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&calls);
+        let tokenizer: Tokenizer = "3:Fizz,5:Buzz,numeric".parse().unwrap();
+        let tokenizer =
+            tokenizer.with_progress(4, move |i, t| recorded.lock().unwrap().push((i, t)));
+        let output = tokenizer
+            .produce_output(10, FormattingOptions::default())
+            .unwrap();
+        assert_eq!(output, "12Fizz4BuzzFizz78FizzBuzz");
+        assert_eq!(*calls.lock().unwrap(), [(4, 10), (8, 10)]);
+    }
+
+    #[test]
+    fn test_cancellation() {
+        let token = CancellationToken::new();
+        let cancel = token.clone();
+        let tokenizer: Tokenizer = "3:Fizz,5:Buzz,numeric".parse().unwrap();
+        // Cancels the run from within, after the fifth iteration.
+        let tokenizer = tokenizer
+            .with_cancellation(token)
+            .with_progress(5, move |_, _| cancel.cancel());
+        let err = tokenizer
+            .produce_output(u32::MAX, space_separated())
+            .unwrap_err();
+        let expected = FizzBuzzError::Cancelled {
+            progress: Progress {
+                iterations: 5,
+                tokens: 5,
+                bytes: 15,
+                output: "1 2 Fizz 4 Buzz".to_string(),
+            },
+        };
+        assert_eq!(err, expected);
+        assert_eq!(err.to_string(), "The run was cancelled after 5 iterations.");
+    }
+}
@@ -0,0 +1,313 @@
+//! Counting the tokens of a run without producing them.
+//!
+//! If every rule matches exactly at the multiples of a divisor, see
+//! [`crate::TokenCondition::divisor`], the number of iterations at which exactly a
+//! given set of rules matches follows from inclusion–exclusion over the least
+//! common multiples of all subsets of the rules. Resolving each set of matching
+//! rules once then gives the number of tokens of every rule in closed form.
+
+use crate::error::{FizzBuzzError, Result};
+use crate::formatting::FormattingOptions;
+use crate::integer::{gcd, lcm, Integer};
+use crate::range::{Direction, IterationRange};
+use crate::resolution::ResolutionStrategy;
+use crate::tokenizer::{Tokenizer, Tokens};
+
+/// The biggest number of rules, that are counted in closed form.
+/// The number of subsets of the rules grows exponentially.
+const MAX_RULES: usize = 16;
+
+/// The number of tokens each rule produced within a run, see [`Tokenizer::count`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenCounts {
+    wins: Vec<u64>,
+    silent: u64,
+}
+
+impl TokenCounts {
+    /// The number of iterations, to whose token each rule contributed, in the
+    /// order in which the rules were passed to the [`Tokenizer`].
+    pub fn wins(&self) -> &[u64] {
+        &self.wins
+    }
+
+    /// The number of iterations, that did not produce a token.
+    pub fn silent(&self) -> u64 {
+        self.silent
+    }
+}
+
+impl<N: Integer> Tokenizer<N> {
+    /// Counts how many tokens every rule produces for the iterations of `range`,
+    /// without rendering them.
+    ///
+    /// If every rule reports a [`crate::TokenCondition::divisor`], the counts are
+    /// computed in closed form, independent of the length of the range. Otherwise,
+    /// or for a [custom](ResolutionStrategy::Custom) resolution, for stateful rules
+    /// and for more than 16 rules, the range is iterated.
+    ///
+    /// # Errors
+    /// Returns [`crate::error::FizzBuzzError::Overflow`], if a range with more
+    /// than `u64::MAX` iterations would have to be iterated.
+    pub fn count(&self, range: impl Into<IterationRange<N>>) -> Result<TokenCounts> {
+        let range = range.into();
+        match self.count_closed_form(&range) {
+            Some(counts) => Ok(counts),
+            None if range.len() == u64::MAX => Err(FizzBuzzError::Overflow {
+                operation: "counting more than `u64::MAX` iterations",
+            }),
+            None => self.count_iterating(&range),
+        }
+    }
+
+    /// Returns `None`, if the counts can not be computed in closed form.
+    pub(crate) fn count_closed_form(&self, range: &IterationRange<N>) -> Option<TokenCounts> {
+        let rules = self.rules();
+        if rules.len() > MAX_RULES
+            || self.is_stateful()
+            || matches!(self.resolution(), ResolutionStrategy::Custom(_))
+        {
+            return None;
+        }
+        let divisors = rules
+            .iter()
+            .map(|r| r.divisor().filter(|d| *d > N::zero())?.to_i128())
+            .collect::<Option<Vec<_>>>()?;
+        let progression = Progression::new(range)?;
+
+        // The least common multiple of every subset of the rules, where bit `n` of the
+        // index stands for rule `n`. `None`, if it is not representable in an `i128`.
+        let subsets = 1usize << rules.len();
+        let mut lcms = vec![Some(1i128); subsets];
+        for mask in 1..subsets {
+            let n = mask.trailing_zeros() as usize;
+            lcms[mask] = lcms[mask & (mask - 1)].and_then(|l| lcm(&l, &divisors[n]));
+        }
+
+        // First the number of iterations at which at least the rules of a subset match,
+        // then by inclusion–exclusion the number at which exactly those rules match.
+        let mut exact: Vec<i128> = lcms.iter().map(|l| progression.multiples(*l)).collect();
+        for n in 0..rules.len() {
+            for mask in 0..subsets {
+                if mask & (1 << n) == 0 {
+                    exact[mask] -= exact[mask | (1 << n)];
+                }
+            }
+        }
+
+        let mut counts = TokenCounts {
+            wins: vec![0; rules.len()],
+            silent: 0,
+        };
+        let mut matches = Vec::new();
+        let mut winners = Vec::new();
+        for (mask, count) in exact.into_iter().enumerate().filter(|(_, c)| *c > 0) {
+            let count = u64::try_from(count).ok()?;
+            matches.clear();
+            matches.extend((0..rules.len()).filter(|n| mask & (1 << n) != 0));
+            // Failed resolutions are reported for the actual iteration by iterating.
+            self.resolve(range.start(), &matches, &mut winners).ok()?;
+            if winners.is_empty() {
+                counts.silent += count;
+            }
+            for n in &winners {
+                counts.wins[*n] += count;
+            }
+        }
+        Some(counts)
+    }
+
+    fn count_iterating(&self, range: &IterationRange<N>) -> Result<TokenCounts> {
+        let options = FormattingOptions {
+            separator: None,
+            case: None,
+        };
+        let mut tokens = Tokens::new(self, &options, range, 0, range.len());
+        let mut counts = TokenCounts {
+            wins: vec![0; self.rules().len()],
+            silent: range.len(),
+        };
+        while let Some(advanced) = tokens.advance() {
+            advanced?;
+            counts.silent -= 1;
+            for n in tokens.winners() {
+                counts.wins[*n] += 1;
+            }
+        }
+        Ok(counts)
+    }
+}
+
+/// The values of an [`IterationRange`] as `start + k * step` for `k` in `0..len`.
+struct Progression {
+    start: i128,
+    /// Negative for descending ranges.
+    step: i128,
+    len: i128,
+}
+
+impl Progression {
+    /// Returns `None`, if the values are not representable in an `i128`, or if
+    /// the length of the range saturated.
+    fn new<N: Integer>(range: &IterationRange<N>) -> Option<Self> {
+        let len = range.len();
+        if len == u64::MAX {
+            return None;
+        }
+        let step = range.step().to_i128()?;
+        Some(Self {
+            start: range.start().to_i128()?,
+            step: match range.direction() {
+                Direction::Ascending => step,
+                Direction::Descending => -step,
+            },
+            len: i128::from(len),
+        })
+    }
+
+    /// The number of values, that are multiples of `m`. `None` stands for a
+    /// multiple that exceeds every `i128`, so only zero is a multiple of it.
+    fn multiples(&self, m: Option<i128>) -> i128 {
+        let Some(m) = m else {
+            let zero_at = (self.start.checked_rem(self.step) == Some(0))
+                .then(|| self.start.checked_div(self.step)?.checked_neg())
+                .flatten()
+                .filter(|k| (0..self.len).contains(k));
+            return i128::from(zero_at.is_some());
+        };
+        // Solves `step * k ≡ -start (mod m)` for `k`.
+        let a = self.step.rem_euclid(m);
+        let b = (m - self.start.rem_euclid(m)) % m;
+        let g = gcd(&a, &m);
+        if b % g != 0 {
+            return 0;
+        }
+        let m = m / g;
+        let first = mul_mod(b / g, inverse(a / g, m), m);
+        if first >= self.len {
+            return 0;
+        }
+        (self.len - 1 - first) / m + 1
+    }
+}
+
+/// `a * b mod m` for `0 <= a, b < m`, without overflowing.
+fn mul_mod(a: i128, b: i128, m: i128) -> i128 {
+    if let Some(product) = a.checked_mul(b) {
+        return product % m;
+    }
+    let (mut a, mut b, mut product) = (a, b, 0i128);
+    while b > 0 {
+        if b & 1 == 1 {
+            product = (product - (m - a)).rem_euclid(m);
+        }
+        a = (a - (m - a)).rem_euclid(m);
+        b >>= 1;
+    }
+    product
+}
+
+/// The inverse of `a` modulo `m`, for coprime `a` and `m`.
+fn inverse(a: i128, m: i128) -> i128 {
+    if m == 1 {
+        return 0;
+    }
+    let (mut r0, mut r1) = (a, m);
+    let (mut s0, mut s1) = (1i128, 0i128);
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (s0, s1) = (s1, s0 - q * s1);
+    }
+    s0.rem_euclid(m)
+}
+
+#[cfg(test)]
+mod test {
+    use super::mul_mod;
+    use crate::test::fizz_buzz_rules;
+    use crate::{
+        error::FizzBuzzError, ConcatenationOrder, ConsecutiveTokens, Direction, IterationRange,
+        ResolutionStrategy, TokenCondition, Tokenizer, Traditional,
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_count() {
+        let tokenizer = Tokenizer::new(fizz_buzz_rules::<u64>());
+        let counts = tokenizer.count(100).unwrap();
+        assert_eq!(counts.wins(), &[53, 27, 14, 6]);
+        assert_eq!(counts.silent(), 0);
+
+        let counts = tokenizer.count(1_000_000_000_000).unwrap();
+        let expected = [
+            533_333_333_333,
+            266_666_666_667,
+            133_333_333_334,
+            66_666_666_666,
+        ];
+        assert_eq!(counts.wins(), &expected);
+
+        // Concatenated tokens count for every contributing rule.
+        let rules: Vec<Box<dyn TokenCondition>> = vec![
+            Box::new(Traditional::new("Fizz", 1, vec![3]).unwrap()),
+            Box::new(Traditional::new("Buzz", 1, vec![5]).unwrap()),
+        ];
+        let tokenizer = Tokenizer::new(rules).with_resolution(ResolutionStrategy::Concatenate(
+            ConcatenationOrder::Registration,
+        ));
+        let counts = tokenizer.count(30).unwrap();
+        assert_eq!(counts.wins(), &[10, 6]);
+        assert_eq!(counts.silent(), 16);
+    }
+
+    #[test]
+    fn test_closed_form_equivalence() {
+        let tokenizer = Tokenizer::new(fizz_buzz_rules::<i64>());
+        let ranges = [
+            IterationRange::from(1000),
+            IterationRange::new(-500, 500, true, 1, Direction::Ascending).unwrap(),
+            IterationRange::new(1000, -1000, false, 7, Direction::Descending).unwrap(),
+            IterationRange::new(i64::MIN, i64::MIN + 1000, true, 3, Direction::Ascending).unwrap(),
+            IterationRange::new(i64::MAX, i64::MAX - 1000, true, 10, Direction::Descending)
+                .unwrap(),
+        ];
+        for range in ranges {
+            let closed_form = tokenizer.count_closed_form(&range).unwrap();
+            let iterated = tokenizer.count_iterating(&range).unwrap();
+            assert_eq!(closed_form, iterated, "{range:?}");
+        }
+    }
+
+    #[test]
+    fn test_count_fallback() {
+        // `ConsecutiveTokens` can not be described by a divisor.
+        let mut rules = fizz_buzz_rules::<u32>();
+        rules.push(Box::new(
+            ConsecutiveTokens::new("Fizz", "+", 1, 3, vec![5]).unwrap(),
+        ));
+        let tokenizer = Tokenizer::new(rules);
+        let range = IterationRange::from(100);
+        assert_eq!(tokenizer.count_closed_form(&range), None);
+        assert_eq!(tokenizer.count(range).unwrap().wins(), &[53, 0, 14, 6, 27]);
+
+        // The number of iterations does not fit into the counts.
+        let rules: Vec<Box<dyn TokenCondition<u128>>> = vec![Box::new(
+            ConsecutiveTokens::new("Fizz", "+", 1, 3, vec![5]).unwrap(),
+        )];
+        let range = IterationRange::new(0, u128::MAX, true, 1, Direction::Ascending).unwrap();
+        let err = Tokenizer::new(rules).count(range).unwrap_err();
+        let expected = FizzBuzzError::Overflow {
+            operation: "counting more than `u64::MAX` iterations",
+        };
+        assert_eq!(err, expected);
+    }
+
+    #[test]
+    fn test_mul_mod() {
+        let m = i128::MAX - 1;
+        assert_eq!(mul_mod(m - 1, m - 1, m), 1);
+        assert_eq!(mul_mod(m - 1, 2, m), m - 2);
+        assert_eq!(mul_mod(7, 8, 10), 6);
+    }
+}
@@ -1,9 +1,137 @@
+use std::io;
+
+use crate::limits::{Limit, Progress};
+
 pub type Result<T> = std::result::Result<T, FizzBuzzError>;
 
+/// The errors of the `FizzBuzz` game. New variants may be added in the future.
 #[derive(Debug, thiserror::Error, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum FizzBuzzError {
+    /// A divisor of the rule producing the token `rule` is zero. The `position`
+    /// counts the divisors of the rule from 0. For [`crate::ConsecutiveTokens`],
+    /// the divisor is at position 0 and its rivals follow.
+    #[error(
+        "The rule `{rule}` has a zero divisor at position {position}. No value other than zero is a multiple of zero."
+    )]
+    ZeroDivisor { rule: String, position: usize },
+    /// A [`crate::RuleSet`] without any rules was built.
+    #[error("The set of rules is empty, so no iteration would produce a token.")]
+    EmptyRuleSet,
+    /// The step of an iteration range is zero or negative.
+    #[error("The step of an iteration range has to be bigger than zero, but is {step}.")]
+    InvalidStep { step: String },
+    /// An iteration range without values.
+    #[error("The iteration range from {start} to {end} does not contain any values.")]
+    EmptyRange { start: String, end: String },
+    /// The result of an `operation` is not representable.
+    #[error("Arithmetic overflow: {operation}.")]
+    Overflow { operation: &'static str },
+    /// Several matching rules share the highest priority and the
+    /// [`crate::TieBreak::Error`] policy is configured.
     #[error(
-        "Passed zero! FizzBuzz only operates on `natural numbers` (integers bigger than zero)."
+        "The rules `{first}` and `{second}` match with the same priority at iteration {iteration}."
     )]
-    NonZeroValue,
+    AmbiguousRules {
+        first: String,
+        second: String,
+        iteration: String,
+    },
+    /// An occurrence query for a rule, that can not be located arithmetically,
+    /// scanned the maximum number of iterations without a result.
+    #[error("No occurrence found within the search limit of {limit} iterations.")]
+    SearchLimit { limit: u64 },
+    /// A query referred to a rule of a [`crate::Tokenizer`] by an `index`,
+    /// that it has no rule for.
+    #[error("The tokenizer has no rule with index {index}.")]
+    UnknownRule { index: usize },
+    /// Building a [`crate::RuleSet`] failed for at least one of its rules.
+    #[error("Invalid rules: {}", list(errors))]
+    InvalidRules { errors: Vec<RuleError> },
+    /// A priority was set on a [`crate::RuleSetBuilder`] before any rule was added, or
+    /// for a rule, whose priority can not be set.
+    #[error("The priority {priority} can only be set for divisibility and consecutive rules.")]
+    MisplacedPriority { priority: u32 },
+    /// A rule specification does not follow the grammar, see [`crate::Tokenizer::from_str`](std::str::FromStr).
+    /// The `column` counts the characters of the specification from 1.
+    #[error("Invalid rule specification at column {column}: expected {expected}, found {found}.")]
+    InvalidSpec {
+        column: usize,
+        expected: String,
+        found: String,
+    },
+    /// A number of a rule specification is not representable.
+    #[error("Invalid number `{text}` at column {column} of the rule specification.")]
+    InvalidSpecNumber { column: usize, text: String },
+    /// A rule specification contains a zero divisor.
+    #[error("Passed zero as a divisor at column {column} of the rule specification.")]
+    SpecZeroDivisor { column: usize },
+    /// A configuration document could not be loaded, see [`crate::GameConfig`].
+    /// The `line` and `column` count from 1.
+    #[cfg(feature = "serde")]
+    #[error("Invalid {format} configuration{}: {message}", location(*line, *column))]
+    Config {
+        format: &'static str,
+        line: Option<usize>,
+        column: Option<usize>,
+        message: String,
+    },
+    /// A run exceeded one of the [`crate::Limits`] of its [`crate::Tokenizer`]
+    /// and was stopped after the `progress` it reached.
+    #[error("The run exceeded its budget of {limit} after {} iterations.", progress.iterations)]
+    LimitExceeded { limit: Limit, progress: Progress },
+    /// A run was stopped by the [`crate::CancellationToken`] of its [`crate::Tokenizer`]
+    /// after the `progress` it reached.
+    #[error("The run was cancelled after {} iterations.", progress.iterations)]
+    Cancelled { progress: Progress },
+    /// Writing the output into an [`io::Write`] sink failed.
+    /// The original [`io::Error`] is not kept, as it can neither be cloned nor compared.
+    #[error("Could not write output ({kind}): {message}")]
+    Io {
+        kind: io::ErrorKind,
+        message: String,
+    },
+    /// The rule producing the token `rule` failed to render it at `iteration`,
+    /// see [`crate::TokenCondition::write_token`].
+    #[error("The rule `{rule}` could not render its token at iteration {iteration}.")]
+    Render { rule: String, iteration: String },
+    /// Writing the token of an iteration into a [`std::fmt::Write`] sink failed.
+    /// The `position` counts the iterations of the run from 1. The sink may hold
+    /// parts of the token, as [`std::fmt::Error`] does not tell how much was written.
+    #[error("Could not write the token of iteration {position} into the output.")]
+    Fmt { position: u64 },
+}
+
+/// The error of a single rule of a [`crate::RuleSet`]. Its message leaves out
+/// the `name`, as the messages of the errors of rules already name them.
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+#[error("rule {index}: {error}")]
+pub struct RuleError {
+    /// The position, at which the rule was added.
+    pub index: usize,
+    pub name: String,
+    pub error: FizzBuzzError,
+}
+
+fn list(errors: &[RuleError]) -> String {
+    let errors: Vec<_> = errors.iter().map(ToString::to_string).collect();
+    errors.join("; ")
+}
+
+#[cfg(feature = "serde")]
+fn location(line: Option<usize>, column: Option<usize>) -> String {
+    match (line, column) {
+        (Some(line), Some(column)) => format!(" at line {line}, column {column}"),
+        (Some(line), None) => format!(" at line {line}"),
+        _ => String::new(),
+    }
+}
+
+impl From<io::Error> for FizzBuzzError {
+    fn from(err: io::Error) -> Self {
+        Self::Io {
+            kind: err.kind(),
+            message: err.to_string(),
+        }
+    }
 }
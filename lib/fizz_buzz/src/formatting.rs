@@ -1,6 +1,14 @@
+use std::fmt::{self, Write};
+
 /// Options that are passed to the [`crate::tokenizer::Tokenizer`].
 /// This set of rules configures the shape of the resulting output
 /// string.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(deny_unknown_fields)
+)]
 pub struct FormattingOptions {
     /// Will be appended to each token expect for the last.
     pub separator: Option<String>,
@@ -8,6 +16,12 @@ pub struct FormattingOptions {
     pub case: Option<Case>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
 pub enum Case {
     Lower,
     Upper,
@@ -15,11 +29,11 @@ pub enum Case {
 }
 
 impl FormattingOptions {
-    /// For now the current iteration value `i` and the number
-    /// of total iterations of the run `t` are passed into the
+    /// For now the `position` of the current iteration within the run (starting at 1)
+    /// and the number of `total` iterations of the run are passed into the
     /// formatting function but not used. This is done, because
     /// some formatting rules might depend on this information.
-    pub fn apply_formatting(&self, s: &mut String, _i: u32, _t: u32) {
+    pub fn apply_formatting(&self, s: &mut String, _position: u64, _total: u64) {
         if let Some(case) = &self.case {
             match case {
                 Case::Lower => *s = s.to_lowercase(),
@@ -27,4 +41,97 @@ impl FormattingOptions {
             }
         }
     }
+
+    /// Streaming variant of [`FormattingOptions::apply_formatting`], that writes the
+    /// formatted `token` into `out` without allocating. The output is the same.
+    pub fn write_formatted(
+        &self,
+        token: &str,
+        out: &mut impl fmt::Write,
+        _position: u64,
+        _total: u64,
+    ) -> fmt::Result {
+        match &self.case {
+            None => out.write_str(token),
+            // The lowercase sigma depends on its neighbours, which only `str::to_lowercase` knows.
+            Some(Case::Lower) if token.contains('Σ') => out.write_str(&token.to_lowercase()),
+            Some(case) => CaseWriter::new(case, out).write_str(token),
+        }
+    }
+}
+
+/// Converts the case of everything that is written through it on the fly.
+struct CaseWriter<'a, W: fmt::Write> {
+    case: &'a Case,
+    inner: &'a mut W,
+}
+
+impl<'a, W: fmt::Write> CaseWriter<'a, W> {
+    fn new(case: &'a Case, inner: &'a mut W) -> Self {
+        Self { case, inner }
+    }
+}
+
+impl<W: fmt::Write> fmt::Write for CaseWriter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        // Converted characters are collected on the stack and passed on in batches.
+        let mut buffer = [0u8; 64];
+        let mut len = 0;
+        for c in s.chars() {
+            let converted: &mut dyn Iterator<Item = char> = match self.case {
+                Case::Lower => &mut c.to_lowercase(),
+                Case::Upper => &mut c.to_uppercase(),
+            };
+            for c in converted {
+                if len + c.len_utf8() > buffer.len() {
+                    self.inner.write_str(as_str(&buffer[..len]))?;
+                    len = 0;
+                }
+                len += c.encode_utf8(&mut buffer[len..]).len();
+            }
+        }
+        self.inner.write_str(as_str(&buffer[..len]))
+    }
+}
+
+/// The buffer of a [`CaseWriter`] only ever holds complete characters.
+fn as_str(bytes: &[u8]) -> &str {
+    std::str::from_utf8(bytes).expect("buffer holds complete characters")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Case, FormattingOptions};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_write_formatted() {
+        let options = |case| FormattingOptions {
+            separator: None,
+            case,
+        };
+        let write = |options: FormattingOptions, token: &str| {
+            let mut out = String::new();
+            options.write_formatted(token, &mut out, 1, 1).unwrap();
+            out
+        };
+
+        assert_eq!(write(options(None), "FizzBuzz"), "FizzBuzz");
+        assert_eq!(write(options(Some(Case::Lower)), "FizzBuzz"), "fizzbuzz");
+        assert_eq!(write(options(Some(Case::Upper)), "Fizz+ß"), "FIZZ+SS");
+
+        // Tokens longer than the internal buffer are written in several batches.
+        let token = "äb".repeat(100);
+        assert_eq!(write(options(Some(Case::Upper)), &token), "ÄB".repeat(100));
+
+        let mut token = "FizzBuzz".to_string();
+        options(Some(Case::Lower)).apply_formatting(&mut token, 1, 1);
+        assert_eq!(token, "fizzbuzz");
+
+        // Both convert a final sigma like `str::to_lowercase` does.
+        assert_eq!(write(options(Some(Case::Lower)), "ΣΟΦΟΣ"), "σοφος");
+        let mut token = "ΣΟΦΟΣ".to_string();
+        options(Some(Case::Lower)).apply_formatting(&mut token, 1, 1);
+        assert_eq!(token, "σοφος");
+    }
 }
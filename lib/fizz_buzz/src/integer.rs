@@ -0,0 +1,300 @@
+//! The numeric domain of the `FizzBuzz` game.
+//!
+//! All iteration values and divisors are of a type implementing [`Integer`].
+//! The trait is implemented for all primitive integer types, `u32` is used
+//! wherever no other type is specified. With the `bigint` feature enabled, it
+//! is also implemented for the arbitrary-precision types [`BigInt`](crate::BigInt)
+//! and [`BigUint`](crate::BigUint).
+
+use std::fmt::{Debug, Display};
+use std::ops::{Add, Div, Mul, Rem, Sub};
+
+/// An integer type that can be used as iteration value and divisor of the `FizzBuzz` game.
+///
+/// # Zero and negative values
+/// - Zero is a multiple of every divisor, so every divisor based rule
+///   matches at zero.
+/// - Divisibility does not depend on the sign: `-6` is a multiple of `3` and
+///   of `-3`. Negative divisors therefore behave like their absolute values.
+/// - Zero itself is not a valid divisor, as no value other than zero is a multiple of it.
+/// - Rules that depend on prior iterations, like [`crate::ConsecutiveTokens`],
+///   count away from zero. `-4` is evaluated like `4`.
+pub trait Integer:
+    Clone
+    + Ord
+    + Display
+    + Debug
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Rem<Output = Self>
+    + Send
+    + Sync
+    + 'static
+{
+    fn zero() -> Self;
+
+    fn one() -> Self;
+
+    fn is_zero(&self) -> bool {
+        *self == Self::zero()
+    }
+
+    fn is_negative(&self) -> bool;
+
+    /// The absolute value. Saturates at the biggest value of the type for
+    /// the smallest value of a signed type.
+    fn abs(&self) -> Self;
+
+    /// Returns `true`, if `divisor` divides `self` cleanly, independent of the signs.
+    /// Zero is only a multiple of zero.
+    fn is_multiple_of(&self, divisor: &Self) -> bool;
+
+    fn checked_add(&self, rhs: &Self) -> Option<Self>;
+
+    fn checked_sub(&self, rhs: &Self) -> Option<Self>;
+
+    fn checked_mul(&self, rhs: &Self) -> Option<Self>;
+
+    /// Converts a `u64` into the type, if it is representable.
+    fn from_u64(value: u64) -> Option<Self>;
+
+    /// Converts the value into a `u64`, if it is representable.
+    fn to_u64(&self) -> Option<u64>;
+
+    /// Converts the value into an `i128`, if it is representable.
+    fn to_i128(&self) -> Option<i128>;
+
+    /// Converts an `i128` into the type, if it is representable.
+    fn from_i128(value: i128) -> Option<Self>;
+}
+
+macro_rules! impl_integer {
+    ($($t:ty),* $(,)?) => {
+        $(
+            impl Integer for $t {
+                fn zero() -> Self {
+                    0
+                }
+
+                fn one() -> Self {
+                    1
+                }
+
+                fn is_negative(&self) -> bool {
+                    *self < Self::zero()
+                }
+
+                fn abs(&self) -> Self {
+                    if self.is_negative() {
+                        Self::zero().saturating_sub(*self)
+                    } else {
+                        *self
+                    }
+                }
+
+                fn is_multiple_of(&self, divisor: &Self) -> bool {
+                    if *divisor == 0 {
+                        return *self == 0;
+                    }
+                    // The only failing case left is `MIN % -1`, which is a clean division.
+                    self.checked_rem(*divisor).is_none_or(|r| r == 0)
+                }
+
+                fn checked_add(&self, rhs: &Self) -> Option<Self> {
+                    <$t>::checked_add(*self, *rhs)
+                }
+
+                fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+                    <$t>::checked_sub(*self, *rhs)
+                }
+
+                fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+                    <$t>::checked_mul(*self, *rhs)
+                }
+
+                fn from_u64(value: u64) -> Option<Self> {
+                    Self::try_from(value).ok()
+                }
+
+                fn to_u64(&self) -> Option<u64> {
+                    u64::try_from(*self).ok()
+                }
+
+                fn to_i128(&self) -> Option<i128> {
+                    i128::try_from(*self).ok()
+                }
+
+                fn from_i128(value: i128) -> Option<Self> {
+                    Self::try_from(value).ok()
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// The greatest common divisor of two non-negative values.
+pub(crate) fn gcd<N: Integer>(a: &N, b: &N) -> N {
+    let (mut a, mut b) = (a.clone(), b.clone());
+    while !b.is_zero() {
+        let r = a % b.clone();
+        a = b;
+        b = r;
+    }
+    a
+}
+
+/// The least common multiple of two positive values.
+/// Returns `None`, if it is not representable in the domain of `N`.
+pub(crate) fn lcm<N: Integer>(a: &N, b: &N) -> Option<N> {
+    (a.clone() / gcd(a, b)).checked_mul(b)
+}
+
+/// Arbitrary-precision integers, that never overflow.
+#[cfg(feature = "bigint")]
+mod bigint {
+    use super::Integer;
+    use num_bigint::{BigInt, BigUint, Sign};
+
+    impl Integer for BigInt {
+        fn zero() -> Self {
+            BigInt::ZERO
+        }
+
+        fn one() -> Self {
+            BigInt::from(1u8)
+        }
+
+        fn is_negative(&self) -> bool {
+            self.sign() == Sign::Minus
+        }
+
+        fn abs(&self) -> Self {
+            BigInt::from(self.magnitude().clone())
+        }
+
+        fn is_multiple_of(&self, divisor: &Self) -> bool {
+            if divisor.is_zero() {
+                return self.is_zero();
+            }
+            (self % divisor).is_zero()
+        }
+
+        fn checked_add(&self, rhs: &Self) -> Option<Self> {
+            Some(self + rhs)
+        }
+
+        fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+            Some(self - rhs)
+        }
+
+        fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+            Some(self * rhs)
+        }
+
+        fn from_u64(value: u64) -> Option<Self> {
+            Some(Self::from(value))
+        }
+
+        fn to_u64(&self) -> Option<u64> {
+            u64::try_from(self).ok()
+        }
+
+        fn to_i128(&self) -> Option<i128> {
+            i128::try_from(self).ok()
+        }
+
+        fn from_i128(value: i128) -> Option<Self> {
+            Some(Self::from(value))
+        }
+    }
+
+    impl Integer for BigUint {
+        fn zero() -> Self {
+            BigUint::ZERO
+        }
+
+        fn one() -> Self {
+            BigUint::from(1u8)
+        }
+
+        fn is_negative(&self) -> bool {
+            false
+        }
+
+        fn abs(&self) -> Self {
+            self.clone()
+        }
+
+        fn is_multiple_of(&self, divisor: &Self) -> bool {
+            if divisor.is_zero() {
+                return self.is_zero();
+            }
+            (self % divisor).is_zero()
+        }
+
+        fn checked_add(&self, rhs: &Self) -> Option<Self> {
+            Some(self + rhs)
+        }
+
+        fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+            (self >= rhs).then(|| self - rhs)
+        }
+
+        fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+            Some(self * rhs)
+        }
+
+        fn from_u64(value: u64) -> Option<Self> {
+            Some(Self::from(value))
+        }
+
+        fn to_u64(&self) -> Option<u64> {
+            u64::try_from(self).ok()
+        }
+
+        fn to_i128(&self) -> Option<i128> {
+            i128::try_from(self).ok()
+        }
+
+        fn from_i128(value: i128) -> Option<Self> {
+            BigUint::try_from(value).ok()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Integer;
+
+    #[test]
+    fn test_integer_semantics() {
+        // -------------------------   Zero   ------------------------- //
+        assert!(Integer::is_multiple_of(&0u32, &7));
+        assert!(!Integer::is_multiple_of(&7u32, &0));
+        assert!(Integer::is_multiple_of(&0u32, &0));
+
+        // -------------------------  Negatives  ------------------------- //
+        assert!(Integer::is_multiple_of(&-6i64, &3));
+        assert!(Integer::is_multiple_of(&-6i64, &-3));
+        assert!(Integer::is_multiple_of(&6i64, &-3));
+        assert!(!Integer::is_multiple_of(&-7i64, &3));
+        assert!(Integer::is_multiple_of(&i64::MIN, &-1));
+
+        assert_eq!(Integer::abs(&-4i8), 4);
+        assert_eq!(Integer::abs(&i8::MIN), i8::MAX);
+        assert_eq!(Integer::abs(&4u8), 4);
+    }
+
+    #[test]
+    fn test_gcd_lcm() {
+        assert_eq!(super::gcd(&12u32, &18), 6);
+        assert_eq!(super::gcd(&7u32, &0), 7);
+        assert_eq!(super::lcm(&4u32, &6), Some(12));
+        assert_eq!(super::lcm(&1u32, &15), Some(15));
+        assert_eq!(super::lcm(&200u8, &3), None);
+    }
+}
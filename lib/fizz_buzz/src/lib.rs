@@ -1,15 +1,51 @@
 pub mod error;
 
+mod compiled;
+#[cfg(feature = "serde")]
+mod config;
+mod context;
+mod control;
+mod count;
 mod formatting;
+mod integer;
+mod limits;
+mod lint;
+mod occurrence;
+#[cfg(feature = "parallel")]
+mod parallel;
 mod presets;
+mod range;
+mod resolution;
+mod rule_set;
+mod spec;
+#[cfg(feature = "async")]
+mod stream;
+mod token;
 mod token_condition;
 mod tokenizer;
 
 // API:
+pub use compiled::CompiledTokenizer;
+#[cfg(feature = "serde")]
+pub use config::{GameConfig, RuleConfig};
+pub use context::{EvaluationContext, History, Record};
+pub use control::CancellationToken;
+pub use count::TokenCounts;
 pub use formatting::{Case, FormattingOptions};
+pub use integer::Integer;
+pub use limits::{Limit, Limits, Progress};
+pub use lint::{Diagnostic, RuleRef};
+#[cfg(feature = "bigint")]
+pub use num_bigint::{BigInt, BigUint};
 pub use presets::{ConsecutiveTokens, Numeric, Traditional};
-pub use token_condition::TokenCondition;
-pub use tokenizer::Tokenizer;
+pub use range::{Direction, IterationRange};
+pub use resolution::{ConcatenationOrder, ResolutionStrategy, Resolver, TieBreak};
+pub use rule_set::{RuleSet, RuleSetBuilder};
+#[cfg(feature = "async")]
+pub use stream::TokenStream;
+pub use token::Token;
+pub use token_condition::{ThreadSafety, TokenCondition};
+pub use tokenizer::{Tokenizer, Tokens};
 
 #[cfg(test)]
 mod test;
@@ -0,0 +1,214 @@
+//! Resource budgets of a run, see [`Tokenizer::with_limits`](crate::Tokenizer::with_limits).
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::error::FizzBuzzError;
+
+/// Bounds the resources, that producing the output of a run may consume.
+/// Every budget is unlimited by default.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// The maximum number of iterations, that are evaluated.
+    pub max_iterations: Option<u64>,
+    /// The maximum number of bytes of the formatted output, including separators.
+    pub max_output_bytes: Option<u64>,
+    /// The maximum number of bytes of the unformatted token of a single iteration.
+    pub max_token_len: Option<usize>,
+    /// The maximum wall-clock time of a run, measured from its start. The clock
+    /// is checked every 1024 iterations, so a run may take slightly longer.
+    pub time_limit: Option<Duration>,
+}
+
+impl Limits {
+    /// Whether no budget is configured.
+    pub fn is_unlimited(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// The budget of the [`Limits`], that a run exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Iterations(u64),
+    OutputBytes(u64),
+    TokenLength(usize),
+    Time(Duration),
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Iterations(max) => write!(f, "at most {max} iterations"),
+            Self::OutputBytes(max) => write!(f, "at most {max} bytes of output"),
+            Self::TokenLength(max) => write!(f, "at most {max} bytes per token"),
+            Self::Time(max) => write!(f, "at most {max:?} of run time"),
+        }
+    }
+}
+
+/// How far a run got, before it was stopped by its [`Limits`] or cancelled.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Progress {
+    /// The number of evaluated iterations.
+    pub iterations: u64,
+    /// The number of tokens, that were produced within the limits.
+    pub tokens: u64,
+    /// The number of bytes of output, that were produced within the limits.
+    pub bytes: u64,
+    /// The output, that was produced within the limits. Only filled by
+    /// [`Tokenizer::produce_output`](crate::Tokenizer::produce_output), as the
+    /// sinks of the other methods already hold it.
+    pub output: String,
+}
+
+impl FizzBuzzError {
+    /// Attaches the `output`, that a run produced before it exceeded its limits
+    /// or was cancelled.
+    pub(crate) fn with_output(self, output: String) -> Self {
+        match self {
+            Self::LimitExceeded {
+                limit,
+                mut progress,
+            } => {
+                progress.output = output;
+                Self::LimitExceeded { limit, progress }
+            }
+            Self::Cancelled { mut progress } => {
+                progress.output = output;
+                Self::Cancelled { progress }
+            }
+            err => err,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Limit, Limits, Progress};
+    use crate::error::FizzBuzzError;
+    use crate::test::space_separated;
+    use crate::Tokenizer;
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+
+    fn tokenizer(limits: Limits) -> Tokenizer {
+        "3:Fizz,5:Buzz,15:FizzBuzz,numeric"
+            .parse::<Tokenizer>()
+            .unwrap()
+            .with_limits(limits)
+    }
+
+    #[test]
+    fn test_limits() {
+        let limits = Limits {
+            max_iterations: Some(5),
+            ..Limits::default()
+        };
+        let err = tokenizer(limits.clone())
+            .produce_output(6, space_separated())
+            .unwrap_err();
+        let expected = FizzBuzzError::LimitExceeded {
+            limit: Limit::Iterations(5),
+            progress: Progress {
+                iterations: 5,
+                tokens: 5,
+                bytes: 15,
+                output: "1 2 Fizz 4 Buzz".to_string(),
+            },
+        };
+        assert_eq!(err, expected);
+        // Runs within the limits are not affected.
+        let output = tokenizer(limits)
+            .produce_output(5, space_separated())
+            .unwrap();
+        assert_eq!(output, "1 2 Fizz 4 Buzz");
+
+        // The output never exceeds its budget.
+        let limits = Limits {
+            max_output_bytes: Some(10),
+            ..Limits::default()
+        };
+        let err = tokenizer(limits)
+            .produce_output(15, space_separated())
+            .unwrap_err();
+        let expected = FizzBuzzError::LimitExceeded {
+            limit: Limit::OutputBytes(10),
+            progress: Progress {
+                iterations: 5,
+                tokens: 4,
+                bytes: 10,
+                output: "1 2 Fizz 4".to_string(),
+            },
+        };
+        assert_eq!(err, expected);
+
+        let limits = Limits {
+            max_token_len: Some(4),
+            ..Limits::default()
+        };
+        let mut sink = Vec::new();
+        let err = tokenizer(limits)
+            .write_to(20, &space_separated(), &mut sink)
+            .unwrap_err();
+        let expected = FizzBuzzError::LimitExceeded {
+            limit: Limit::TokenLength(4),
+            progress: Progress {
+                iterations: 15,
+                tokens: 14,
+                bytes: 48,
+                output: String::new(),
+            },
+        };
+        assert_eq!(err, expected);
+        assert_eq!(sink.len(), 48);
+    }
+
+    #[test]
+    fn test_stops_after_error() {
+        let limits = Limits {
+            max_token_len: Some(3),
+            ..Limits::default()
+        };
+        let tokenizer = tokenizer(limits);
+        let options = space_separated();
+        let mut tokens = tokenizer.iter(7, &options);
+        assert_eq!(tokens.next(), Some(Ok("1".to_string())));
+        assert_eq!(tokens.next(), Some(Ok("2".to_string())));
+        assert!(matches!(
+            tokens.next(),
+            Some(Err(FizzBuzzError::LimitExceeded {
+                limit: Limit::TokenLength(3),
+                ..
+            }))
+        ));
+        assert_eq!(tokens.next(), None);
+        assert_eq!(tokens.next(), None);
+    }
+
+    #[test]
+    fn test_time_limit() {
+        let limits = Limits {
+            time_limit: Some(Duration::ZERO),
+            ..Limits::default()
+        };
+        let tokenizer = tokenizer(limits);
+        let err = tokenizer.iter(u32::MAX, &space_separated()).next().unwrap();
+        let expected = FizzBuzzError::LimitExceeded {
+            limit: Limit::Time(Duration::ZERO),
+            progress: Progress::default(),
+        };
+        assert_eq!(err, Err(expected));
+        assert_eq!(
+            err.unwrap_err().to_string(),
+            "The run exceeded its budget of at most 0ns of run time after 0 iterations."
+        );
+
+        // Compiled tokenizers respect the limits of their rules.
+        let err = tokenizer
+            .compile()
+            .produce_output(10, space_separated())
+            .unwrap_err();
+        assert!(matches!(err, FizzBuzzError::LimitExceeded { .. }));
+    }
+}
@@ -0,0 +1,290 @@
+//! Static analysis of the rules of a [`Tokenizer`].
+//!
+//! Rules that report a [`crate::TokenCondition::divisor`] match exactly at the
+//! multiples of their divisor, so a rule whose divisor is a multiple of the divisor
+//! of a rule that beats it can never win. If every rule reports a divisor, the
+//! closed-form counts of [`Tokenizer::count`] over one period of the rules reveal
+//! all rules that never win and all residues that do not produce a token.
+
+use std::fmt;
+
+use crate::count::TokenCounts;
+use crate::integer::Integer;
+use crate::occurrence::{clamp, first_true};
+use crate::range::{Direction, IterationRange};
+use crate::resolution::ResolutionStrategy;
+use crate::tokenizer::Tokenizer;
+
+/// A rule of a [`Tokenizer`], as referred to by a [`Diagnostic`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleRef {
+    /// The position of the rule within the rules of the [`Tokenizer`].
+    pub index: usize,
+    /// The [name](crate::TokenCondition::name) of the rule.
+    pub name: String,
+}
+
+impl fmt::Display for RuleRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rule {} (`{}`)", self.index, self.name)
+    }
+}
+
+/// A finding of [`Tokenizer::lint`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diagnostic<N: Integer = u32> {
+    /// `rule` never produces a token, as `by` matches wherever it matches and
+    /// wins over it, without a tie of their priorities.
+    ShadowedRule { rule: RuleRef, by: RuleRef },
+    /// `rule` never produces a token, as it loses all ties with other rules
+    /// of the same priority.
+    DeadRule { rule: RuleRef },
+    /// `silent` out of every `period` consecutive iterations do not produce a
+    /// token, the first of them being `first`. The `period` is `None`, if it
+    /// exceeds the domain of `N`, in which case `silent` counts from zero to
+    /// the biggest value of `N`.
+    UncoveredResidues {
+        period: Option<N>,
+        silent: u64,
+        first: N,
+    },
+    /// `rule` produces the same token as `first`.
+    DuplicateToken { rule: RuleRef, first: RuleRef },
+    /// `rule` matches at exactly the same iterations as `first`.
+    DuplicateDivisor { rule: RuleRef, first: RuleRef },
+}
+
+impl<N: Integer> fmt::Display for Diagnostic<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ShadowedRule { rule, by } => {
+                write!(f, "{rule} never produces a token, it is shadowed by {by}")
+            }
+            Self::DeadRule { rule } => write!(f, "{rule} never produces a token"),
+            Self::UncoveredResidues {
+                period: Some(period),
+                silent,
+                first,
+            } => write!(
+                f,
+                "{silent} of every {period} iterations produce no token, starting at {first}"
+            ),
+            Self::UncoveredResidues {
+                period: None,
+                silent,
+                first,
+            } => write!(
+                f,
+                "{silent} iterations produce no token, starting at {first}"
+            ),
+            Self::DuplicateToken { rule, first } => {
+                write!(f, "{rule} produces the same token as {first}")
+            }
+            Self::DuplicateDivisor { rule, first } => {
+                write!(f, "{rule} matches at the same iterations as {first}")
+            }
+        }
+    }
+}
+
+impl<N: Integer> Tokenizer<N> {
+    /// Analyses the rules for mistakes, without running the game.
+    ///
+    /// Rules with the same token are reported for all rules. Everything else is
+    /// only analysed for rules that report a [`crate::TokenCondition::divisor`]:
+    /// rules with the same divisor and rules that are shadowed by a single other
+    /// rule. If every rule reports a divisor, rules that never win because of the
+    /// [`crate::TieBreak`] and iterations without a token are found as well.
+    /// Rules resolved by a [custom](ResolutionStrategy::Custom) resolution are
+    /// not analysed for shadowing.
+    pub fn lint(&self) -> Vec<Diagnostic<N>> {
+        let rules = self.rules();
+        let reference = |index: usize| RuleRef {
+            index,
+            name: rules[index].name().into_owned(),
+        };
+        let divisors: Vec<Option<N>> = rules
+            .iter()
+            .map(|r| r.divisor().filter(|d| *d > N::zero()))
+            .collect();
+        let mut diagnostics = Vec::new();
+
+        for n in 0..rules.len() {
+            if let Some(first) = (0..n).find(|m| rules[*m].name() == rules[n].name()) {
+                diagnostics.push(Diagnostic::DuplicateToken {
+                    rule: reference(n),
+                    first: reference(first),
+                });
+            }
+            if let Some(first) =
+                (0..n).find(|m| divisors[n].is_some() && divisors[*m] == divisors[n])
+            {
+                diagnostics.push(Diagnostic::DuplicateDivisor {
+                    rule: reference(n),
+                    first: reference(first),
+                });
+            }
+        }
+
+        let shadowed: Vec<Option<usize>> = (0..rules.len())
+            .map(|n| self.shadowing(n, &divisors))
+            .collect();
+        for (n, by) in shadowed.iter().enumerate() {
+            if let Some(by) = by {
+                diagnostics.push(Diagnostic::ShadowedRule {
+                    rule: reference(n),
+                    by: reference(*by),
+                });
+            }
+        }
+
+        let Some(period) = self.arithmetic_period() else {
+            return diagnostics;
+        };
+        // One full period of residues, as far as it is representable.
+        let end = clamp::<N>(0, period - 1);
+        let Some(counts) = self.residue_counts(end) else {
+            return diagnostics;
+        };
+        for (n, wins) in counts.wins().iter().enumerate() {
+            if *wins == 0 && shadowed[n].is_none() {
+                diagnostics.push(Diagnostic::DeadRule { rule: reference(n) });
+            }
+        }
+        if counts.silent() > 0 {
+            let first = first_true(0, end, |x| Some(self.residue_counts(x)?.silent() > 0));
+            if let Some(first) = first.and_then(N::from_i128) {
+                diagnostics.push(Diagnostic::UncoveredResidues {
+                    period: (end == period - 1).then(|| N::from_i128(period)).flatten(),
+                    silent: counts.silent(),
+                    first,
+                });
+            }
+        }
+        diagnostics
+    }
+
+    /// The first rule, that matches wherever the rule `n` matches and beats it
+    /// without a tie.
+    fn shadowing(&self, n: usize, divisors: &[Option<N>]) -> Option<usize> {
+        if matches!(self.resolution(), ResolutionStrategy::Custom(_)) {
+            return None;
+        }
+        let rules = self.rules();
+        let divisor = divisors[n].as_ref()?;
+        let mut winners = Vec::new();
+        (0..divisors.len()).find(|m| {
+            let implied = divisors[*m].as_ref().is_some_and(|d| {
+                *m != n
+                    && divisor.is_multiple_of(d)
+                    && rules[*m].get_priority() != rules[n].get_priority()
+            });
+            let matches = [n.min(*m), n.max(*m)];
+            implied
+                && self.resolve(divisor, &matches, &mut winners).is_ok()
+                && !winners.contains(&n)
+        })
+    }
+
+    /// The closed-form counts of the iterations `0..=end`.
+    fn residue_counts(&self, end: i128) -> Option<TokenCounts> {
+        let range = IterationRange::new(
+            N::zero(),
+            N::from_i128(end)?,
+            true,
+            N::one(),
+            Direction::Ascending,
+        )
+        .ok()?;
+        self.count_closed_form(&range)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Diagnostic, RuleRef};
+    use crate::{ResolutionStrategy, RuleSet, Tokenizer};
+    use pretty_assertions::assert_eq;
+
+    fn rule(index: usize, name: &str) -> RuleRef {
+        RuleRef {
+            index,
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_lint() {
+        let tokenizer: Tokenizer = "3:Fizz,5:Buzz,15:FizzBuzz,numeric".parse().unwrap();
+        assert_eq!(tokenizer.lint(), []);
+
+        let tokenizer: Tokenizer = "2&3:Fizz@2,6:Fezz@1,4:Buzz@1,3:Buzz@1".parse().unwrap();
+        let expected = [
+            Diagnostic::DuplicateDivisor {
+                rule: rule(1, "Fezz"),
+                first: rule(0, "Fizz"),
+            },
+            Diagnostic::DuplicateToken {
+                rule: rule(3, "Buzz"),
+                first: rule(2, "Buzz"),
+            },
+            Diagnostic::ShadowedRule {
+                rule: rule(1, "Fezz"),
+                by: rule(0, "Fizz"),
+            },
+            Diagnostic::UncoveredResidues {
+                period: Some(12),
+                silent: 6,
+                first: 1,
+            },
+        ];
+        assert_eq!(tokenizer.lint(), expected);
+        let message = expected[2].to_string();
+        assert_eq!(
+            message,
+            "rule 1 (`Fezz`) never produces a token, it is shadowed by rule 0 (`Fizz`)"
+        );
+    }
+
+    #[test]
+    fn test_dead_rules() {
+        // "Fezz" loses every tie with "Buzz", as the last rule wins ties.
+        let tokenizer: Tokenizer = "numeric,6:Fezz@1,3:Buzz@1".parse().unwrap();
+        let expected = [Diagnostic::DeadRule {
+            rule: rule(1, "Fezz"),
+        }];
+        assert_eq!(tokenizer.lint(), expected);
+
+        // With the first match winning, priorities only matter for shadowing.
+        let tokenizer: Tokenizer = RuleSet::builder()
+            .divisible_by(1, "Fizz")
+            .divisible_by(2, "Buzz")
+            .fallback_numeric()
+            .build()
+            .unwrap()
+            .with_resolution(ResolutionStrategy::FirstMatch);
+        let expected = [
+            Diagnostic::DuplicateDivisor {
+                rule: rule(2, "Numeric"),
+                first: rule(0, "Fizz"),
+            },
+            Diagnostic::ShadowedRule {
+                rule: rule(2, "Numeric"),
+                by: rule(0, "Fizz"),
+            },
+            Diagnostic::DeadRule {
+                rule: rule(1, "Buzz"),
+            },
+        ];
+        assert_eq!(tokenizer.lint(), expected);
+
+        // Periods beyond the domain of `N`.
+        let tokenizer: Tokenizer<u8> = "7:Fizz,11:Buzz,13:Bazz".parse().unwrap();
+        let expected = [Diagnostic::UncoveredResidues {
+            period: None,
+            silent: 183,
+            first: 1,
+        }];
+        assert_eq!(tokenizer.lint(), expected);
+    }
+}
@@ -0,0 +1,294 @@
+//! Queries for the iterations at which a rule produces its token.
+//!
+//! If every rule matches exactly at the multiples of a divisor, the winners of an
+//! iteration only depend on its residue modulo the least common multiple `L` of all
+//! divisors. A rule that wins anywhere therefore wins within every `L` consecutive
+//! iterations. The occurrences within such a window are located by bisecting the
+//! closed-form counts of [`Tokenizer::count`], which take the priority shadowing
+//! between the rules into account. All other rule sets are scanned sequentially.
+
+use crate::error::{FizzBuzzError, Result};
+use crate::formatting::FormattingOptions;
+use crate::integer::{lcm, Integer};
+use crate::range::{Direction, IterationRange};
+use crate::tokenizer::{Tokenizer, Tokens};
+
+/// The maximum number of iterations, that are scanned for an occurrence.
+const SCAN_LIMIT: u64 = 1 << 20;
+
+impl<N: Integer> Tokenizer<N> {
+    /// The first iteration `i >= from`, to whose token the rule with the index
+    /// `rule` contributes. Returns `None`, if there is no such iteration within
+    /// the domain of `N`.
+    ///
+    /// Rules that can not be located arithmetically are scanned for at most 2^20
+    /// iterations, before [`FizzBuzzError::SearchLimit`] is returned. The scan starts
+    /// at `from`, so rules that depend on the history of a run see an empty history there.
+    /// Returns [`FizzBuzzError::UnknownRule`], if `rule` is not the index of a rule
+    /// of the tokenizer.
+    pub fn next_occurrence(&self, rule: usize, from: N) -> Result<Option<N>> {
+        self.check_rule(rule)?;
+        if let Some(found) = self.locate(rule, &from, Direction::Ascending) {
+            return Ok(found);
+        }
+        self.scan(rule, from, Direction::Ascending, 1)
+    }
+
+    /// The last iteration `i <= from`, to whose token the rule with the index
+    /// `rule` contributes. See [`Tokenizer::next_occurrence`].
+    pub fn previous_occurrence(&self, rule: usize, from: N) -> Result<Option<N>> {
+        self.check_rule(rule)?;
+        if let Some(found) = self.locate(rule, &from, Direction::Descending) {
+            return Ok(found);
+        }
+        self.scan(rule, from, Direction::Descending, 1)
+    }
+
+    /// The `nth` iteration of the traditional run `1, 2, 3, ...`, to whose token the
+    /// rule with the index `rule` contributes, counting from 1.
+    /// Returns `None` for `nth = 0`. See [`Tokenizer::next_occurrence`].
+    pub fn nth_occurrence(&self, rule: usize, nth: u64) -> Result<Option<N>> {
+        self.check_rule(rule)?;
+        if nth == 0 {
+            return Ok(None);
+        }
+        if let Some(found) = self.locate_nth(rule, nth) {
+            return Ok(found);
+        }
+        self.scan(rule, N::one(), Direction::Ascending, nth)
+    }
+
+    fn check_rule(&self, rule: usize) -> Result<()> {
+        if rule < self.rules().len() {
+            Ok(())
+        } else {
+            Err(FizzBuzzError::UnknownRule { index: rule })
+        }
+    }
+
+    /// The least common multiple of the divisors of all rules, if every rule has one.
+    pub(crate) fn arithmetic_period(&self) -> Option<i128> {
+        self.rules().iter().try_fold(1, |period, rule| {
+            let divisor = rule.divisor().filter(|d| *d > N::zero())?;
+            lcm(&period, &divisor.to_i128()?)
+        })
+    }
+
+    /// The number of tokens of `rule` within `low..=high` in closed form.
+    fn wins_between(&self, rule: usize, low: i128, high: i128) -> Option<u64> {
+        let range = IterationRange::new(
+            N::from_i128(low)?,
+            N::from_i128(high)?,
+            true,
+            N::one(),
+            Direction::Ascending,
+        )
+        .ok()?;
+        Some(self.count_closed_form(&range)?.wins()[rule])
+    }
+
+    /// Locates the occurrence next to `from` in `direction` arithmetically.
+    /// Returns `None`, if that is not possible.
+    fn locate(&self, rule: usize, from: &N, direction: Direction) -> Option<Option<N>> {
+        let period = self.arithmetic_period()?;
+        let from = from.to_i128()?;
+        let found = match direction {
+            Direction::Ascending => {
+                let end = clamp::<N>(from, from.saturating_add(period - 1));
+                if self.wins_between(rule, from, end)? == 0 {
+                    return Some(None);
+                }
+                first_true(from, end, |x| Some(self.wins_between(rule, from, x)? > 0))?
+            }
+            Direction::Descending => {
+                let start = clamp::<N>(from, from.saturating_sub(period - 1));
+                if self.wins_between(rule, start, from)? == 0 {
+                    return Some(None);
+                }
+                if self.wins_between(rule, from, from)? > 0 {
+                    return N::from_i128(from).map(Some);
+                }
+                // the last `x` with a win in `x..=from`
+                first_true(start, from, |x| {
+                    Some(self.wins_between(rule, x, from)? == 0)
+                })
+                .map_or(from, |x| x - 1)
+            }
+        };
+        N::from_i128(found).map(Some)
+    }
+
+    /// Locates the `nth` occurrence arithmetically, by skipping whole periods.
+    /// Returns `None`, if that is not possible.
+    fn locate_nth(&self, rule: usize, nth: u64) -> Option<Option<N>> {
+        let period = self.arithmetic_period()?;
+        let per_period = self.wins_between(rule, 1, period)?;
+        if per_period == 0 {
+            return Some(None);
+        }
+        let skipped = (nth - 1) / per_period;
+        let remaining = nth - skipped * per_period;
+        let within = first_true(1, period, |x| {
+            Some(self.wins_between(rule, 1, x)? >= remaining)
+        })?;
+        let found = i128::from(skipped)
+            .checked_mul(period)
+            .and_then(|offset| offset.checked_add(within));
+        Some(found.and_then(N::from_i128))
+    }
+
+    /// Scans up to [`SCAN_LIMIT`] iterations from `from` in `direction` for the
+    /// `nth` occurrence of `rule`.
+    fn scan(&self, rule: usize, from: N, direction: Direction, nth: u64) -> Result<Option<N>> {
+        let end = farthest(&from, SCAN_LIMIT - 1, direction);
+        let range = IterationRange::new(from, end, true, N::one(), direction)?;
+        // A range shorter than the limit ends at the bounds of the domain of `N`.
+        let exhaustive = range.len() < SCAN_LIMIT;
+
+        let options = FormattingOptions {
+            separator: None,
+            case: None,
+        };
+        let mut tokens = Tokens::new(self, &options, &range, 0, range.len());
+        let mut found = None;
+        for _ in 0..nth {
+            found = tokens.advance_to_win(rule).transpose()?;
+            if found.is_none() {
+                break;
+            }
+        }
+        match found {
+            None if !exhaustive => Err(FizzBuzzError::SearchLimit { limit: SCAN_LIMIT }),
+            found => Ok(found),
+        }
+    }
+}
+
+/// The smallest `x` within `low..=high`, for which `predicate` holds, assuming
+/// it holds for all values from `x` on. Returns `high`, if it only holds there,
+/// and `None`, if `predicate` fails.
+pub(crate) fn first_true(
+    mut low: i128,
+    mut high: i128,
+    mut predicate: impl FnMut(i128) -> Option<bool>,
+) -> Option<i128> {
+    while low < high {
+        // `high - low` may not be representable as an `i128`, but as an `u128`.
+        let half = (high as u128).wrapping_sub(low as u128) / 2;
+        let mid = low + half as i128;
+        if predicate(mid)? {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+    Some(low)
+}
+
+/// The value within `from..=to`, that is farthest from `from` and still in the
+/// domain of `N`. `from` has to be in the domain.
+pub(crate) fn clamp<N: Integer>(from: i128, to: i128) -> i128 {
+    let representable = |x: i128| N::from_i128(x).is_some();
+    if representable(to) {
+        return to;
+    }
+    if to > from {
+        // the first value above the domain, minus one
+        first_true(from, to, |x| Some(!representable(x))).map_or(from, |x| x - 1)
+    } else {
+        first_true(to, from, |x| Some(representable(x))).unwrap_or(from)
+    }
+}
+
+/// The value at most `distance` steps from `from` into `direction`, that is
+/// farthest from `from` and still in the domain of `N`.
+fn farthest<N: Integer>(from: &N, distance: u64, direction: Direction) -> N {
+    let step = |d: u64| {
+        let d = N::from_u64(d)?;
+        match direction {
+            Direction::Ascending => from.checked_add(&d),
+            Direction::Descending => from.checked_sub(&d),
+        }
+    };
+    if let Some(end) = step(distance) {
+        return end;
+    }
+    let (mut low, mut high) = (0, distance);
+    while low + 1 < high {
+        let mid = low + (high - low) / 2;
+        if step(mid).is_some() {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    step(low).unwrap_or_else(|| from.clone())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::fizz_buzz_rules;
+    use crate::{
+        error::FizzBuzzError, ConsecutiveTokens, Numeric, TokenCondition, Tokenizer, Traditional,
+    };
+    use pretty_assertions::assert_eq;
+
+    const FIZZ: usize = 1;
+    const BUZZ: usize = 2;
+    const FIZZ_BUZZ: usize = 3;
+
+    #[test]
+    fn test_arithmetic_occurrences() {
+        let tokenizer = Tokenizer::new(fizz_buzz_rules::<u64>());
+
+        // "Fizz" is shadowed by "FizzBuzz" at 15.
+        assert_eq!(tokenizer.next_occurrence(FIZZ, 13).unwrap(), Some(18));
+        assert_eq!(tokenizer.next_occurrence(FIZZ_BUZZ, 16).unwrap(), Some(30));
+        assert_eq!(tokenizer.previous_occurrence(BUZZ, 16).unwrap(), Some(10));
+        // 0 is a multiple of every divisor, so "FizzBuzz" wins there.
+        assert_eq!(tokenizer.previous_occurrence(BUZZ, 4).unwrap(), None);
+        assert_eq!(tokenizer.nth_occurrence(BUZZ, 1000).unwrap(), Some(7495));
+        assert_eq!(tokenizer.nth_occurrence(FIZZ_BUZZ, 10).unwrap(), Some(150));
+        assert_eq!(tokenizer.nth_occurrence(FIZZ, 0).unwrap(), None);
+
+        // No occurrence left within the domain.
+        let tokenizer = Tokenizer::new(fizz_buzz_rules::<u8>());
+        assert_eq!(
+            tokenizer.next_occurrence(FIZZ_BUZZ, 241).unwrap(),
+            Some(255)
+        );
+        assert_eq!(tokenizer.next_occurrence(BUZZ, 251).unwrap(), None);
+
+        // Negative values.
+        let tokenizer = Tokenizer::new(fizz_buzz_rules::<i32>());
+        assert_eq!(tokenizer.previous_occurrence(FIZZ, -1).unwrap(), Some(-3));
+        assert_eq!(tokenizer.next_occurrence(BUZZ, -14).unwrap(), Some(-10));
+
+        // Unknown rules are reported by every query.
+        let unknown = || Err(FizzBuzzError::UnknownRule { index: 4 });
+        assert_eq!(tokenizer.next_occurrence(4, 1), unknown());
+        assert_eq!(tokenizer.previous_occurrence(4, 1), unknown());
+        assert_eq!(tokenizer.nth_occurrence(4, 1), unknown());
+    }
+
+    #[test]
+    fn test_scanned_occurrences() {
+        let rules: Vec<Box<dyn TokenCondition>> = vec![
+            Box::new(Numeric),
+            Box::new(ConsecutiveTokens::new("Fizz", "+", 1, 3, vec![5]).unwrap()),
+            Box::new(Traditional::new("Buzz", 2, vec![5]).unwrap()),
+        ];
+        let tokenizer = Tokenizer::new(rules);
+        assert_eq!(tokenizer.next_occurrence(1, 13).unwrap(), Some(18));
+        assert_eq!(tokenizer.previous_occurrence(2, 14).unwrap(), Some(10));
+        assert_eq!(tokenizer.nth_occurrence(2, 1000).unwrap(), Some(5000));
+
+        // A rule that never wins within the scan limit.
+        let rules: Vec<Box<dyn TokenCondition>> = vec![
+            Box::new(ConsecutiveTokens::new("Fizz", "+", 1, 3, vec![2]).unwrap()),
+            Box::new(Traditional::new("Buzz", 2, vec![3]).unwrap()),
+        ];
+        let err = Tokenizer::new(rules).next_occurrence(0, 1).unwrap_err();
+        assert_eq!(err, FizzBuzzError::SearchLimit { limit: 1 << 20 });
+    }
+}
@@ -0,0 +1,62 @@
+//! Parallel evaluation of a `FizzBuzz` run.
+//!
+//! The iterations of a run are split into chunks, which are evaluated on the
+//! global `rayon` thread pool and reassembled in their original order afterwards.
+//! As every chunk is evaluated on its own, this is only correct for rules, whose
+//! tokens depend on nothing but the current iteration value. This is the case
+//! for the [`Traditional`](crate::Traditional), [`Numeric`](crate::Numeric) and
+//! [`ConsecutiveTokens`](crate::ConsecutiveTokens) presets. Runs with stateful
+//! rules are evaluated sequentially.
+
+use rayon::prelude::*;
+
+use crate::error::Result;
+use crate::formatting::FormattingOptions;
+use crate::integer::Integer;
+use crate::range::IterationRange;
+use crate::tokenizer::{Tokenizer, Tokens};
+
+/// Number of iterations that are evaluated in one go by a single thread.
+const CHUNK_LEN: u64 = 1 << 16;
+
+impl<N: Integer> Tokenizer<N> {
+    /// Produces the same output as [`Tokenizer::produce_output`], but evaluates the
+    /// iterations of `range` in parallel. If any rule is
+    /// [stateful](crate::TokenCondition::is_stateful), or the run is bound by
+    /// [`crate::Limits`], reports its progress or can be cancelled, the iterations
+    /// are evaluated sequentially.
+    pub fn produce_output_parallel(
+        &self,
+        range: impl Into<IterationRange<N>>,
+        options: FormattingOptions,
+    ) -> Result<String> {
+        if self.is_stateful() || self.is_controlled() {
+            return self.produce_output(range, options);
+        }
+        let range = range.into();
+        let total = range.len();
+        let separator = options.separator.as_deref().unwrap_or_default();
+
+        let chunks = range
+            .split(CHUNK_LEN)
+            .into_par_iter()
+            .enumerate()
+            .map(|(n, chunk)| {
+                let offset = n as u64 * CHUNK_LEN;
+                let mut output = String::new();
+                let tokens =
+                    Tokens::new(self, &options, &chunk, offset, total).write_joined(&mut output)?;
+                Ok((output, tokens))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut output = String::with_capacity(chunks.iter().map(|(c, _)| c.len()).sum());
+        for (chunk, _) in chunks.iter().filter(|(_, tokens)| *tokens > 0) {
+            if !output.is_empty() {
+                output.push_str(separator);
+            }
+            output.push_str(chunk);
+        }
+        Ok(output)
+    }
+}
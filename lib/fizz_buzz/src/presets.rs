@@ -3,7 +3,12 @@
 //! to serve as examples on how to use the API so that users can create
 //! their own variants.
 
+use std::borrow::Cow;
+
 use crate::error::{FizzBuzzError, Result};
+use crate::integer::Integer;
+use crate::spec;
+use crate::token::Token;
 use crate::token_condition::TokenCondition;
 
 /// The traditional token creation rule, that produces the defined `token`,
@@ -13,16 +18,25 @@ use crate::token_condition::TokenCondition;
 /// divisor is passed. It can also produce output like "FizzBuzz" when
 /// multiple divisors are passed withing the `divisors` vector.
 #[derive(Debug)]
-pub struct Traditional {
-    token: &'static str,
+pub struct Traditional<N: Integer = u32> {
+    token: Cow<'static, str>,
     priority: u32,
-    divisors: Vec<u32>,
+    divisors: Vec<N>,
 }
 
-impl Traditional {
-    pub fn new(token: &'static str, priority: u32, divisors: Vec<u32>) -> Result<Self> {
-        if divisors.contains(&0) {
-            return Err(FizzBuzzError::NonZeroValue);
+impl<N: Integer> Traditional<N> {
+    /// The `token` can be a static or a runtime-provided string.
+    pub fn new(
+        token: impl Into<Cow<'static, str>>,
+        priority: u32,
+        divisors: Vec<N>,
+    ) -> Result<Self> {
+        let token = token.into();
+        if let Some(position) = divisors.iter().position(Integer::is_zero) {
+            return Err(FizzBuzzError::ZeroDivisor {
+                rule: token.into_owned(),
+                position,
+            });
         }
         Ok(Self {
             token,
@@ -32,36 +46,87 @@ impl Traditional {
     }
 }
 
-impl TokenCondition for Traditional {
-    fn tokenize(&self, _i: u32) -> String {
+impl<N: Integer> TokenCondition<N> for Traditional<N> {
+    fn tokenize(&self, _i: N) -> String {
         self.token.to_string()
     }
 
-    fn condition(&self, i: u32) -> bool {
-        self.divisors.iter().all(|d| i % d == 0)
+    fn condition(&self, i: N) -> bool {
+        self.divisors.iter().all(|d| i.is_multiple_of(d))
+    }
+
+    fn evaluate(&self, i: N) -> Option<Token<'_, N>> {
+        self.condition(i).then_some(Token::Text(&self.token))
+    }
+
+    /// The least common multiple of all divisors.
+    fn period(&self) -> Option<N> {
+        self.divisors.iter().try_fold(N::one(), |period, d| {
+            let magnitude = d.abs();
+            // The magnitude of the smallest value of a signed type is not representable.
+            if d.is_negative() && !(d.clone() + magnitude.clone()).is_zero() {
+                return None;
+            }
+            crate::integer::lcm(&period, &magnitude)
+        })
+    }
+
+    /// All divisors divide exactly the multiples of their least common multiple.
+    fn divisor(&self) -> Option<N> {
+        self.period()
     }
 
     fn get_priority(&self) -> u32 {
         self.priority
     }
+
+    fn name(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.token)
+    }
+
+    fn spec(&self) -> Option<String> {
+        let divisors = spec::join(&self.divisors);
+        let token = spec::escape(&self.token);
+        Some(format!("{divisors}:{token}@{}", self.priority))
+    }
 }
 
 /// This token creation rule is generally used as a fallback rule in the `FizzBuzz` game.
 /// It has the smallest possible priority and its print condition always evaluates to true.
 /// Its token is the number of the current iteration converted into a `String`.
 pub struct Numeric;
-impl TokenCondition for Numeric {
-    fn tokenize(&self, i: u32) -> String {
+impl<N: Integer> TokenCondition<N> for Numeric {
+    fn tokenize(&self, i: N) -> String {
         i.to_string()
     }
 
-    fn condition(&self, _i: u32) -> bool {
+    fn condition(&self, _i: N) -> bool {
         true
     }
 
+    fn evaluate(&self, i: N) -> Option<Token<'_, N>> {
+        Some(Token::Value(i))
+    }
+
+    fn period(&self) -> Option<N> {
+        Some(N::one())
+    }
+
+    fn divisor(&self) -> Option<N> {
+        Some(N::one())
+    }
+
     fn get_priority(&self) -> u32 {
         0
     }
+
+    fn name(&self) -> Cow<'_, str> {
+        Cow::Borrowed("Numeric")
+    }
+
+    fn spec(&self) -> Option<String> {
+        Some(spec::NUMERIC.to_string())
+    }
 }
 
 /// This rule preset can be used to produce output that is akin to the
@@ -74,39 +139,56 @@ impl TokenCondition for Numeric {
 /// appended to the defined `token`.
 /// The `suffix` is applied as many times as prior clean divisions by `divisor`
 /// have occurred, without being interrupted by one of its `rivals`.
+///
+/// Zero interrupts every sequence of clean divisions and negative iteration
+/// values are evaluated like their absolute values, so sequences grow away from zero.
 #[derive(Debug)]
-pub struct ConsecutiveTokens {
-    token: &'static str,
+pub struct ConsecutiveTokens<N: Integer = u32> {
+    token: Cow<'static, str>,
     priority: u32,
-    divisor: u32,
-    rivals: Vec<u32>,
-    suffix: &'static str,
+    divisor: N,
+    rivals: Vec<N>,
+    suffix: Cow<'static, str>,
 }
 
-impl ConsecutiveTokens {
+impl<N: Integer> ConsecutiveTokens<N> {
+    /// The `token` and the `suffix` can be static or runtime-provided strings.
     pub fn new(
-        token: &'static str,
-        suffix: &'static str,
+        token: impl Into<Cow<'static, str>>,
+        suffix: impl Into<Cow<'static, str>>,
         priority: u32,
-        divisor: u32,
-        rivals: Vec<u32>,
+        divisor: N,
+        rivals: Vec<N>,
     ) -> Result<Self> {
-        if rivals.contains(&0) || divisor == 0 {
-            return Err(FizzBuzzError::NonZeroValue);
+        let token = token.into();
+        let zero = std::iter::once(&divisor)
+            .chain(&rivals)
+            .position(Integer::is_zero);
+        if let Some(position) = zero {
+            return Err(FizzBuzzError::ZeroDivisor {
+                rule: token.into_owned(),
+                position,
+            });
         }
 
+        // Divisibility does not depend on the sign, but the number of
+        // divisions is calculated on absolute values.
         Ok(Self {
             token,
             priority,
-            divisor,
-            rivals,
-            suffix,
+            divisor: magnitude(&divisor),
+            rivals: rivals.iter().map(magnitude).collect(),
+            suffix: suffix.into(),
         })
     }
 
     /// Calculates the number of clean divisions within 1..=i of `divisor`, up to the last
-    /// clean division of the range by any member of `rivals`.
+    /// clean division of the range by any member of `rivals`. Runs in constant time
+    /// per rival, as the last clean division by a rival is `i - i % rival`.
     /// Returns `0`, if the current iteration `i` can not be cleanly divided by `divisor`.
+    /// Negative values of `i` are evaluated like their absolute values, which is done
+    /// without negating `i`, as the smallest value of a signed type has no representable
+    /// absolute value.
     ///
     /// I.e. for `i = 8`, `divisor = 2` and `rivals = &[5]`:
     ///     -   prior to 8, which is cleanly divided by `divisor`, `divisor` also divides
@@ -117,135 +199,274 @@ impl ConsecutiveTokens {
     /// # Panics
     /// This function assumes nor `divisor` neither any member of `rivals`
     /// is equal to zero and panics, if this it not the case.
-    fn calculate_uninterrupted_divisions(i: u32, divisor: u32, rivals: &[u32]) -> usize {
-        if i % divisor != 0 {
+    fn calculate_uninterrupted_divisions(i: N, divisor: &N, rivals: &[N]) -> usize {
+        if !i.is_multiple_of(divisor) {
             return 0;
         }
+        let negative = i.is_negative();
 
-        // first clean division
-        if divisor == i {
+        // first clean division. `i + divisor` can not overflow, as a negative divisor
+        // is the smallest value of its type, which among negative values only divides itself.
+        if *divisor == i || (negative && (i.clone() + divisor.clone()).is_zero()) {
             return 1;
         }
 
-        // no rivals
-        if rivals.is_empty() {
-            return (i / divisor) as usize;
+        // The last clean division by any rival between zero and i, or zero if there
+        // is none. For a negative i, all of them are negative and the last one is the smallest.
+        let interrupts = rivals.iter().map(|r| i.clone() - i.clone() % r.clone());
+        let interrupt = if negative {
+            interrupts.min()
+        } else {
+            interrupts.max()
+        };
+        let interrupt = interrupt.unwrap_or_else(N::zero);
+
+        // The number of times `divisor` fits between the last interrupt and `i`, plus one.
+        // If the interrupt itself is a clean division by `divisor`, the "FizzBuzz" case,
+        // it does not count.
+        let delta = i - interrupt;
+        let fizz_buzz = delta.is_multiple_of(divisor);
+        let mut divisions = delta / divisor.clone();
+        if !fizz_buzz && negative {
+            divisions = divisions - N::one();
+        } else if !fizz_buzz {
+            divisions = divisions + N::one();
         }
+        Self::to_count(divisions)
+    }
 
-        // If the smallest rival is already bigger than the current iteration, we can skip the check.
-        if i < *rivals.iter().min().unwrap_or(&0) {
-            let total_divisions = i / divisor;
-            return total_divisions as usize; // save conversion on targets with at least 32 bit arch.
-        }
-
-        let mut uninterrupted_clean_division: Option<u32> = None;
-
-        for r in rivals {
-            let mut last_clean_divide_by_rival = None;
-            // Check backwards for clean divisions
-            for iteration in (1..=i).rev() {
-                if iteration % r == 0 {
-                    last_clean_divide_by_rival = Some(iteration);
-                    break;
-                }
-            }
-
-            // if there was a prior clean division by a rival, the number of uninterrupted
-            // clean divisions by `divisor` is the number of times `divisor` fits between
-            // the rivals last clean division and `i` plus one.
-            if let Some(rival_division) = last_clean_divide_by_rival {
-                let delta = i - rival_division;
-
-                // check for the "FizzBuzz" case, where the last clean division by a rival is also
-                // a clean division by divisor. This is considered an interrupt.
-                let fizz_buzz = delta % divisor == 0;
+    /// Converts a number of divisions, which is negative for negative iterations,
+    /// into a count of suffixes. Numbers of divisions that do not fit into memory
+    /// anyways are saturated.
+    fn to_count(divisions: N) -> usize {
+        divisions
+            .to_i128()
+            .and_then(|d| usize::try_from(d.unsigned_abs()).ok())
+            .unwrap_or(usize::MAX)
+    }
+}
 
-                uninterrupted_clean_division = if let Some(v) = uninterrupted_clean_division {
-                    Some(v.min((delta / divisor) + !fizz_buzz as u32))
-                } else {
-                    Some((delta / divisor) + !fizz_buzz as u32)
-                };
-            }
-        }
-        uninterrupted_clean_division.unwrap_or(0) as usize
+/// The absolute value of the divisor `d`. The smallest value of a signed type is
+/// kept as is, as its absolute value is not representable. It divides only itself
+/// and zero either way.
+fn magnitude<N: Integer>(d: &N) -> N {
+    let abs = d.abs();
+    if d.is_negative() && !(d.clone() + abs.clone()).is_zero() {
+        d.clone()
+    } else {
+        abs
     }
 }
 
-impl TokenCondition for ConsecutiveTokens {
-    fn tokenize(&self, i: u32) -> String {
-        let pluses = Self::calculate_uninterrupted_divisions(i, self.divisor, &self.rivals);
+impl<N: Integer> TokenCondition<N> for ConsecutiveTokens<N> {
+    fn tokenize(&self, i: N) -> String {
+        let pluses = Self::calculate_uninterrupted_divisions(i, &self.divisor, &self.rivals);
         let pulses = pluses.saturating_sub(1); // first occurrence without suffix!
         format!("{}{}", self.token, self.suffix.repeat(pulses))
     }
 
-    fn condition(&self, i: u32) -> bool {
-        Self::calculate_uninterrupted_divisions(i, self.divisor, &self.rivals) > 0
+    fn condition(&self, i: N) -> bool {
+        Self::calculate_uninterrupted_divisions(i, &self.divisor, &self.rivals) > 0
+    }
+
+    fn evaluate(&self, i: N) -> Option<Token<'_, N>> {
+        let divisions = Self::calculate_uninterrupted_divisions(i, &self.divisor, &self.rivals);
+        (divisions > 0).then(|| Token::Repeated {
+            text: &self.token,
+            suffix: &self.suffix,
+            count: divisions - 1, // first occurrence without suffix!
+        })
     }
 
     fn get_priority(&self) -> u32 {
         self.priority
     }
+
+    fn name(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.token)
+    }
+
+    fn spec(&self) -> Option<String> {
+        let token = spec::escape(&self.token);
+        let suffix = spec::escape(&self.suffix);
+        let mut spec = format!("{}:{token}~{suffix}", self.divisor);
+        if !self.rivals.is_empty() {
+            spec = format!("{spec}/{}", spec::join(&self.rivals));
+        }
+        Some(format!("{spec}@{}", self.priority))
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::ConsecutiveTokens;
+    use crate::integer::Integer;
+    use crate::token_condition::TokenCondition;
     use pretty_assertions::assert_eq;
+
+    /// The former implementation of [`ConsecutiveTokens::calculate_uninterrupted_divisions`],
+    /// that scans backwards for the last clean division of every rival.
+    fn scan_uninterrupted_divisions<N: Integer>(i: N, divisor: &N, rivals: &[N]) -> usize {
+        let i = i.abs();
+        if !i.is_multiple_of(divisor) {
+            return 0;
+        }
+        if *divisor == i {
+            return 1;
+        }
+        if rivals.is_empty() || rivals.iter().min().is_some_and(|min| i < *min) {
+            return ConsecutiveTokens::to_count(i / divisor.clone());
+        }
+
+        let mut uninterrupted_clean_division: Option<N> = None;
+        for r in rivals {
+            let mut last_clean_divide_by_rival = None;
+            let mut iteration = i.clone();
+            while !iteration.is_zero() {
+                if iteration.is_multiple_of(r) {
+                    last_clean_divide_by_rival = Some(iteration);
+                    break;
+                }
+                iteration = iteration - N::one();
+            }
+            if let Some(rival_division) = last_clean_divide_by_rival {
+                let delta = i.clone() - rival_division;
+                let fizz_buzz = delta.is_multiple_of(divisor);
+                let mut divisions = delta / divisor.clone();
+                if !fizz_buzz {
+                    divisions = divisions + N::one();
+                }
+                uninterrupted_clean_division = Some(match uninterrupted_clean_division {
+                    Some(v) => v.min(divisions),
+                    None => divisions,
+                });
+            }
+        }
+        uninterrupted_clean_division.map_or(0, ConsecutiveTokens::to_count)
+    }
+
+    #[test]
+    fn test_equivalence_with_scan() {
+        let configurations: &[(u64, &[u64])] = &[
+            (3, &[5]),
+            (5, &[3]),
+            (2, &[3, 7]),
+            (7, &[2, 3, 5]),
+            (4, &[2]),
+            (3, &[6, 9]),
+            (6, &[1000]),
+            (11, &[]),
+        ];
+        for (divisor, rivals) in configurations {
+            for i in 0..=200_000u64 {
+                assert_eq!(
+                    ConsecutiveTokens::calculate_uninterrupted_divisions(i, divisor, rivals),
+                    scan_uninterrupted_divisions(i, divisor, rivals),
+                    "i = {i}, divisor = {divisor}, rivals = {rivals:?}"
+                );
+            }
+        }
+
+        // Negative values and the bounds of a signed domain.
+        let values = (-20_000i64..=20_000).chain([i64::MIN + 1]);
+        for i in values {
+            assert_eq!(
+                ConsecutiveTokens::calculate_uninterrupted_divisions(i, &3, &[5, 7]),
+                scan_uninterrupted_divisions(i, &3, &[5, 7]),
+                "i = {i}"
+            );
+        }
+
+        // The absolute value of the smallest value is only representable as `u64`.
+        let configurations: &[(i64, &[i64])] = &[(2, &[3]), (4, &[3, 5]), (2, &[2048]), (3, &[5])];
+        for (divisor, rivals) in configurations {
+            for i in i64::MIN..i64::MIN + 100 {
+                let rivals_u64: Vec<u64> = rivals.iter().map(|r| r.unsigned_abs()).collect();
+                assert_eq!(
+                    ConsecutiveTokens::calculate_uninterrupted_divisions(i, divisor, rivals),
+                    ConsecutiveTokens::calculate_uninterrupted_divisions(
+                        i.unsigned_abs(),
+                        &divisor.unsigned_abs(),
+                        &rivals_u64
+                    ),
+                    "i = {i}, divisor = {divisor}, rivals = {rivals:?}"
+                );
+            }
+        }
+        assert_eq!(
+            ConsecutiveTokens::calculate_uninterrupted_divisions(i64::MIN, &4, &[3]),
+            1
+        );
+    }
     #[test]
     fn test_uninterrupted_divisions() {
         // ------------------------- Divisor does not divide `i` ------------------------- //
-        let res = ConsecutiveTokens::calculate_uninterrupted_divisions(7, 2, &vec![7]);
+        let res = ConsecutiveTokens::calculate_uninterrupted_divisions(7, &2, &[7]);
         let expected = 0;
         assert_eq!(res, expected);
 
         // --------------------- No prior rival clean divisions  ------------------------- //
-        let res = ConsecutiveTokens::calculate_uninterrupted_divisions(6, 2, &vec![7]);
+        let res = ConsecutiveTokens::calculate_uninterrupted_divisions(6, &2, &[7]);
         let expected = 3;
         assert_eq!(res, expected);
 
         // ---------------------  Interrupted directly before   ------------------------- //
-        let res = ConsecutiveTokens::calculate_uninterrupted_divisions(8, 2, &vec![3]);
+        let res = ConsecutiveTokens::calculate_uninterrupted_divisions(8, &2, &[3]);
         let expected = 1;
         assert_eq!(res, expected);
 
         // ---------------------   Rival smaller than divisor   ------------------------- //
-        let res = ConsecutiveTokens::calculate_uninterrupted_divisions(10, 5, &vec![3]);
+        let res = ConsecutiveTokens::calculate_uninterrupted_divisions(10, &5, &[3]);
         let expected = 1;
         assert_eq!(res, expected);
 
         // ---------------------  Iteration before any division ------------------------- //
-        let res = ConsecutiveTokens::calculate_uninterrupted_divisions(3, 5, &vec![6]);
+        let res = ConsecutiveTokens::calculate_uninterrupted_divisions(3, &5, &[6]);
         let expected = 0;
         assert_eq!(res, expected);
 
         // ---------------------   Check at FizzBuzz condition  ------------------------- //
-        let res = ConsecutiveTokens::calculate_uninterrupted_divisions(6, 2, &vec![3]);
+        let res = ConsecutiveTokens::calculate_uninterrupted_divisions(6, &2, &[3]);
         let expected = 0;
         assert_eq!(res, expected);
 
         // --------------------- Second rival interrupts first  ------------------------- //
-        let res = ConsecutiveTokens::calculate_uninterrupted_divisions(8, 2, &vec![9, 5]);
+        let res = ConsecutiveTokens::calculate_uninterrupted_divisions(8, &2, &[9, 5]);
         let expected = 2;
         assert_eq!(res, expected);
 
         // ---------------------     FizzBuzz second rival      ------------------------- //
-        let res = ConsecutiveTokens::calculate_uninterrupted_divisions(10, 2, &vec![9, 5]);
+        let res = ConsecutiveTokens::calculate_uninterrupted_divisions(10, &2, &[9, 5]);
         let expected = 0;
         assert_eq!(res, expected);
 
         // --------------------- FizzBuzz interrupt second rival ------------------------ //
-        let res = ConsecutiveTokens::calculate_uninterrupted_divisions(24, 2, &vec![9, 5]);
+        let res = ConsecutiveTokens::calculate_uninterrupted_divisions(24, &2, &[9, 5]);
         let expected = 2;
         assert_eq!(res, expected);
 
         // ---------------------           No rivals             ------------------------ //
-        let res = ConsecutiveTokens::calculate_uninterrupted_divisions(24, 2, &vec![]);
+        let res = ConsecutiveTokens::calculate_uninterrupted_divisions(24, &2, &[]);
         let expected = 12;
         assert_eq!(res, expected);
 
         // --------------          No division at checked iteration        -------------- //
-        let res = ConsecutiveTokens::calculate_uninterrupted_divisions(25, 2, &vec![]);
+        let res = ConsecutiveTokens::calculate_uninterrupted_divisions(25, &2, &[]);
         let expected = 0;
         assert_eq!(res, expected);
     }
+
+    #[test]
+    fn test_smallest_divisor() {
+        // Matches only the smallest value itself, not the biggest value.
+        let rule = ConsecutiveTokens::new("Min", "+", 1, i64::MIN, vec![]).unwrap();
+        assert!(rule.condition(i64::MIN));
+        assert!(!rule.condition(i64::MAX));
+        assert!(!rule.condition(i64::MIN / 2));
+
+        // As a rival, the smallest value interrupts only itself.
+        let rule = ConsecutiveTokens::new("Fizz", "+", 1, 2i64, vec![i64::MIN]).unwrap();
+        assert!(!rule.condition(i64::MIN));
+        assert_eq!(rule.tokenize(-4), "Fizz+");
+    }
 }
@@ -0,0 +1,308 @@
+use std::ops::{Range, RangeInclusive};
+
+use crate::error::{FizzBuzzError, Result};
+use crate::integer::Integer;
+
+/// The order in which the values of an [`IterationRange`] are visited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// From `start` upwards to `end`.
+    Ascending,
+    /// From `start` downwards to `end`.
+    Descending,
+}
+
+/// Describes which iteration values a `FizzBuzz` run visits and in which order.
+///
+/// The run starts at `start` and moves by `step` into the configured [`Direction`]
+/// until `end` is reached. Whether `end` itself is visited depends on `end_inclusive`.
+/// I.e. `IterationRange::new(100, 1, true, 1, Direction::Descending)` counts down
+/// from 100 to 1, while `IterationRange::new(1, 100, true, 7, Direction::Ascending)`
+/// plays every 7th number starting at 1.
+///
+/// A plain value `t` converts into the range `1..=t`, which is how the game is
+/// traditionally played.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IterationRange<N: Integer = u32> {
+    start: N,
+    end: N,
+    end_inclusive: bool,
+    step: N,
+    direction: Direction,
+}
+
+impl<N: Integer> IterationRange<N> {
+    /// Creates a new range and validates it.
+    /// Fails, if `step` is not bigger than zero or if the range would not contain any values.
+    pub fn new(
+        start: N,
+        end: N,
+        end_inclusive: bool,
+        step: N,
+        direction: Direction,
+    ) -> Result<Self> {
+        if step <= N::zero() {
+            return Err(FizzBuzzError::InvalidStep {
+                step: step.to_string(),
+            });
+        }
+        let range = Self {
+            start,
+            end,
+            end_inclusive,
+            step,
+            direction,
+        };
+        if range.is_empty() {
+            return Err(FizzBuzzError::EmptyRange {
+                start: range.start.to_string(),
+                end: range.end.to_string(),
+            });
+        }
+        Ok(range)
+    }
+
+    pub fn start(&self) -> &N {
+        &self.start
+    }
+
+    pub fn end(&self) -> &N {
+        &self.end
+    }
+
+    pub fn end_inclusive(&self) -> bool {
+        self.end_inclusive
+    }
+
+    pub fn step(&self) -> &N {
+        &self.step
+    }
+
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// The number of iteration values within the range.
+    /// Saturates at `u64::MAX` for ranges that contain even more values.
+    pub fn len(&self) -> u64 {
+        let Some(last) = self.inclusive_end() else {
+            return 0;
+        };
+        let (low, high) = match self.direction {
+            Direction::Ascending => (&self.start, &last),
+            Direction::Descending => (&last, &self.start),
+        };
+        if low > high {
+            return 0;
+        }
+        let steps = match high.checked_sub(low) {
+            Some(distance) => (distance / self.step.clone()).to_u64(),
+            // The distance is not representable for signed ranges crossing zero,
+            // that span more than half of the domain of the type.
+            None => match (low.to_i128(), high.to_i128(), self.step.to_i128()) {
+                (Some(low), Some(high), Some(step)) => high
+                    .checked_sub(low)
+                    .and_then(|distance| u64::try_from(distance / step).ok()),
+                _ => None,
+            },
+        };
+        steps.map_or(u64::MAX, |steps| steps.saturating_add(1))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The last value of the range, that may be visited.
+    /// Returns `None`, if the exclusive end does not leave any values in the domain of `N`.
+    fn inclusive_end(&self) -> Option<N> {
+        if self.end_inclusive {
+            return Some(self.end.clone());
+        }
+        match self.direction {
+            Direction::Ascending => self.end.checked_sub(&N::one()),
+            Direction::Descending => self.end.checked_add(&N::one()),
+        }
+    }
+
+    /// Returns an iterator over all values of the range in the configured order.
+    pub(crate) fn values(&self) -> Values<N> {
+        let last = self.inclusive_end();
+        Values {
+            next: if self.is_empty() {
+                None
+            } else {
+                Some(self.start.clone())
+            },
+            last: last.unwrap_or_else(N::zero),
+            step: self.step.clone(),
+            direction: self.direction,
+        }
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<N: Integer> IterationRange<N> {
+    /// Splits the range into consecutive parts of `chunk_len` values each, only the
+    /// last part may be shorter. The parts visit the values in the same order as `self`.
+    /// If the distance between the first values of two parts is not representable
+    /// in the domain of `N`, the range is not split.
+    pub(crate) fn split(&self, chunk_len: u64) -> Vec<Self> {
+        let stride = N::from_u64(chunk_len).and_then(|len| len.checked_mul(&self.step));
+        let (Some(stride), Some(last)) = (stride, self.inclusive_end()) else {
+            return vec![self.clone()];
+        };
+        let advance = |value: &N, by: &N| match self.direction {
+            Direction::Ascending => value.checked_add(by).filter(|n| *n <= last),
+            Direction::Descending => value.checked_sub(by).filter(|n| *n >= last),
+        };
+        // `stride` fits into `N`, so the distance between the first and the last
+        // value of a part does as well.
+        let span = stride.clone() - self.step.clone();
+
+        let mut parts = Vec::new();
+        let mut start = (!self.is_empty()).then(|| self.start.clone());
+        while let Some(first) = start {
+            let end = advance(&first, &span).unwrap_or_else(|| last.clone());
+            start = advance(&first, &stride);
+            parts.push(Self {
+                start: first,
+                end,
+                end_inclusive: true,
+                step: self.step.clone(),
+                direction: self.direction,
+            });
+        }
+        parts
+    }
+}
+
+/// The traditional range `1..=t`. For `t < 1` the range is empty, which is not
+/// considered an error for backwards compatibility.
+impl<N: Integer> From<N> for IterationRange<N> {
+    fn from(t: N) -> Self {
+        Self {
+            start: N::one(),
+            end: t,
+            end_inclusive: true,
+            step: N::one(),
+            direction: Direction::Ascending,
+        }
+    }
+}
+
+impl<N: Integer> TryFrom<RangeInclusive<N>> for IterationRange<N> {
+    type Error = FizzBuzzError;
+
+    fn try_from(range: RangeInclusive<N>) -> Result<Self> {
+        let (start, end) = range.into_inner();
+        Self::new(start, end, true, N::one(), Direction::Ascending)
+    }
+}
+
+impl<N: Integer> TryFrom<Range<N>> for IterationRange<N> {
+    type Error = FizzBuzzError;
+
+    fn try_from(range: Range<N>) -> Result<Self> {
+        Self::new(
+            range.start,
+            range.end,
+            false,
+            N::one(),
+            Direction::Ascending,
+        )
+    }
+}
+
+/// Iterator over the values of an [`IterationRange`].
+pub(crate) struct Values<N: Integer> {
+    next: Option<N>,
+    /// The last value that may be visited.
+    last: N,
+    step: N,
+    direction: Direction,
+}
+
+impl<N: Integer> Iterator for Values<N> {
+    type Item = N;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.next.take()?;
+        // The successor of the last value might not be representable,
+        // so the range also ends, if stepping overflows.
+        self.next = match self.direction {
+            Direction::Ascending => value.checked_add(&self.step).filter(|n| *n <= self.last),
+            Direction::Descending => value.checked_sub(&self.step).filter(|n| *n >= self.last),
+        };
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Direction, IterationRange};
+    use crate::error::FizzBuzzError;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_iteration_range() {
+        // -------------------------    Traditional run    ------------------------- //
+        let range = IterationRange::from(5);
+        assert_eq!(range.values().collect::<Vec<_>>(), vec![1, 2, 3, 4, 5]);
+        assert!(IterationRange::from(0).is_empty());
+
+        // -------------------------     Exclusive end     ------------------------- //
+        let range = IterationRange::try_from(3..6).unwrap();
+        assert_eq!(range.len(), 3);
+        assert_eq!(range.values().collect::<Vec<_>>(), vec![3, 4, 5]);
+
+        // -------------------------   Descending & step   ------------------------- //
+        let range = IterationRange::new(20, 1, true, 7, Direction::Descending).unwrap();
+        assert_eq!(range.len(), 3);
+        assert_eq!(range.values().collect::<Vec<_>>(), vec![20, 13, 6]);
+
+        let range = IterationRange::new(21, 0, false, 7, Direction::Descending).unwrap();
+        assert_eq!(range.values().collect::<Vec<_>>(), vec![21, 14, 7]);
+
+        // -------------------------  Bounds of the domain  ------------------------- //
+        let range = IterationRange::new(u32::MAX - 2, u32::MAX, true, 1, Direction::Ascending);
+        assert_eq!(range.unwrap().values().last(), Some(u32::MAX));
+
+        let range = IterationRange::new(0, u32::MAX, true, 1, Direction::Ascending).unwrap();
+        assert_eq!(range.len(), u64::from(u32::MAX) + 1);
+
+        // -------------------------  Signed ranges        ------------------------- //
+        let range = IterationRange::new(-3i8, 3, true, 2, Direction::Ascending).unwrap();
+        assert_eq!(range.values().collect::<Vec<_>>(), vec![-3, -1, 1, 3]);
+
+        let range = IterationRange::new(i8::MAX, i8::MIN, true, 1, Direction::Descending);
+        assert_eq!(range.unwrap().len(), 256);
+
+        // -------------------------   Invalid ranges      ------------------------- //
+        let err = IterationRange::new(1, 10, true, 0, Direction::Ascending).unwrap_err();
+        let expected = FizzBuzzError::InvalidStep {
+            step: "0".to_string(),
+        };
+        assert_eq!(err, expected);
+
+        let err = IterationRange::new(1, 10, true, -1, Direction::Ascending).unwrap_err();
+        let expected = FizzBuzzError::InvalidStep {
+            step: "-1".to_string(),
+        };
+        assert_eq!(err, expected);
+
+        let err = IterationRange::try_from(5..5).unwrap_err();
+        let expected = FizzBuzzError::EmptyRange {
+            start: "5".to_string(),
+            end: "5".to_string(),
+        };
+        assert_eq!(err, expected);
+
+        let err = IterationRange::new(1, 10, true, 1, Direction::Descending).unwrap_err();
+        let expected = FizzBuzzError::EmptyRange {
+            start: "1".to_string(),
+            end: "10".to_string(),
+        };
+        assert_eq!(err, expected);
+    }
+}
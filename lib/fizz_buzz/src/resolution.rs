@@ -0,0 +1,149 @@
+use std::cmp::Ordering;
+
+use crate::error::{FizzBuzzError, Result};
+use crate::integer::Integer;
+use crate::token_condition::{ThreadSafety, TokenCondition};
+
+/// Decides which rules produce the token of an iteration, if the conditions
+/// of several rules are met at the same time.
+#[derive(Default)]
+pub enum ResolutionStrategy<N: Integer = u32> {
+    /// Only the matching rule with the highest priority produces a token.
+    /// Matching rules with the same priority are resolved by the [`TieBreak`] policy.
+    /// This is the default strategy.
+    #[default]
+    HighestPriority,
+    /// The tokens of all matching rules are concatenated in the given order, i.e.
+    /// "Fizz" and "Buzz" produce "FizzBuzz" at multiples of both of their divisors.
+    ///
+    /// Rules with a priority of `0`, like the [`crate::Numeric`] rule, are treated
+    /// as fallbacks. They only produce a token, if no other rule matches. In that
+    /// case a single fallback is chosen like with [`Self::HighestPriority`].
+    Concatenate(ConcatenationOrder),
+    /// The matching rule that was registered first produces the token.
+    FirstMatch,
+    /// A user supplied [`Resolver`] decides.
+    Custom(Box<dyn Resolver<N>>),
+}
+
+/// Decides which rule wins, if several matching rules share the highest priority.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TieBreak {
+    /// The rule that was registered first wins.
+    FirstWins,
+    /// The rule that was registered last wins. This is the default policy.
+    #[default]
+    LastWins,
+    /// A tie is reported as [`FizzBuzzError::AmbiguousRules`].
+    Error,
+}
+
+/// The order in which tokens are concatenated by [`ResolutionStrategy::Concatenate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConcatenationOrder {
+    /// The token of the rule with the highest priority comes first. Rules with
+    /// the same priority are ordered by registration.
+    Priority,
+    /// The order in which the rules were passed to the [`crate::Tokenizer`].
+    Registration,
+}
+
+/// User supplied resolution of matching rules, see [`ResolutionStrategy::Custom`].
+///
+/// Implemented for all functions and closures with a matching signature.
+pub trait Resolver<N: Integer = u32>: ThreadSafety {
+    /// Receives all rules whose conditions are met at iteration `i`, in the order
+    /// of their registration. Returns the positions of the rules within `matches`,
+    /// whose tokens are concatenated to the token of the iteration. Positions
+    /// outside of `matches` are ignored.
+    fn resolve(&self, i: &N, matches: &[&dyn TokenCondition<N>]) -> Vec<usize>;
+}
+
+impl<N, F> Resolver<N> for F
+where
+    N: Integer,
+    F: Fn(&N, &[&dyn TokenCondition<N>]) -> Vec<usize> + ThreadSafety,
+{
+    fn resolve(&self, i: &N, matches: &[&dyn TokenCondition<N>]) -> Vec<usize> {
+        self(i, matches)
+    }
+}
+
+impl<N: Integer> ResolutionStrategy<N> {
+    /// Writes the indices of the `rules`, that produce the token of iteration `i`,
+    /// into `winners`. `matches` holds the indices of all rules whose conditions
+    /// are met at `i` in ascending order.
+    pub(crate) fn resolve(
+        &self,
+        i: &N,
+        rules: &[Box<dyn TokenCondition<N>>],
+        matches: &[usize],
+        tie_break: TieBreak,
+        winners: &mut Vec<usize>,
+    ) -> Result<()> {
+        winners.clear();
+        let priority = |n: &usize| rules[*n].get_priority();
+        match self {
+            Self::HighestPriority => {
+                winners.extend(highest_priority(i, rules, matches, tie_break)?);
+            }
+            Self::FirstMatch => winners.extend(matches.first()),
+            Self::Concatenate(order) => {
+                winners.extend(matches.iter().filter(|n| priority(n) > 0));
+                if winners.is_empty() {
+                    winners.extend(highest_priority(i, rules, matches, tie_break)?);
+                } else if *order == ConcatenationOrder::Priority {
+                    // stable, so equal priorities stay in the order of registration
+                    winners.sort_by_key(|n| std::cmp::Reverse(priority(n)));
+                }
+            }
+            Self::Custom(resolver) => {
+                let candidates: Vec<_> = matches.iter().map(|n| rules[*n].as_ref()).collect();
+                let chosen = resolver.resolve(i, &candidates);
+                winners.extend(chosen.into_iter().filter_map(|c| matches.get(c)));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the index of the matching rule with the highest priority.
+/// Ties are resolved by the `tie_break` policy.
+fn highest_priority<N: Integer>(
+    i: &N,
+    rules: &[Box<dyn TokenCondition<N>>],
+    matches: &[usize],
+    tie_break: TieBreak,
+) -> Result<Option<usize>> {
+    let mut winner: Option<usize> = None;
+    // first tie at the currently highest priority
+    let mut tie = None;
+    for &n in matches {
+        let Some(w) = winner else {
+            winner = Some(n);
+            continue;
+        };
+        match rules[n].get_priority().cmp(&rules[w].get_priority()) {
+            Ordering::Greater => {
+                winner = Some(n);
+                tie = None;
+            }
+            Ordering::Equal => match tie_break {
+                TieBreak::FirstWins => {}
+                TieBreak::LastWins => winner = Some(n),
+                TieBreak::Error => {
+                    tie.get_or_insert((w, n));
+                }
+            },
+            Ordering::Less => {}
+        }
+    }
+    if let Some((first, second)) = tie {
+        return Err(FizzBuzzError::AmbiguousRules {
+            first: rules[first].name().into_owned(),
+            second: rules[second].name().into_owned(),
+            iteration: i.to_string(),
+        });
+    }
+    Ok(winner)
+}
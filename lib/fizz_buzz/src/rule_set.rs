@@ -0,0 +1,431 @@
+//! A fluent builder for the rules of a [`Tokenizer`].
+//!
+//! The builder collects descriptions of the preset rules and only constructs
+//! them in [`RuleSetBuilder::build`], so that every invalid rule is reported at once.
+
+use std::borrow::Cow;
+use std::marker::PhantomData;
+
+use crate::error::{FizzBuzzError, Result, RuleError};
+use crate::integer::{self, Integer};
+use crate::presets::{ConsecutiveTokens, Numeric, Traditional};
+use crate::token_condition::TokenCondition;
+use crate::tokenizer::Tokenizer;
+
+/// The priority of the preset rules, if none is set and priorities are not
+/// assigned [automatically](RuleSetBuilder::auto_priority).
+const DEFAULT_PRIORITY: u32 = 1;
+
+/// Entry point for building the rules of a [`Tokenizer`], see [`RuleSet::builder`].
+pub struct RuleSet<N: Integer = u32> {
+    _integer: PhantomData<N>,
+}
+
+impl<N: Integer> RuleSet<N> {
+    /// Starts an empty set of rules.
+    ///
+    /// ```
+    /// use fizz_buzz::{FormattingOptions, RuleSet, Tokenizer};
+    ///
+    /// let tokenizer: Tokenizer = RuleSet::builder()
+    ///     .fallback_numeric()
+    ///     .divisible_by(3, "Fizz")
+    ///     .divisible_by(5, "Buzz")
+    ///     .divisible_by_all([3, 5], "FizzBuzz")
+    ///     .auto_priority()
+    ///     .build()
+    ///     .unwrap();
+    /// let options = FormattingOptions {
+    ///     separator: Some(" ".to_string()),
+    ///     case: None,
+    /// };
+    /// let output = tokenizer.produce_output(15, options).unwrap();
+    /// assert!(output.ends_with("13 14 FizzBuzz"));
+    /// ```
+    pub fn builder() -> RuleSetBuilder<N> {
+        RuleSetBuilder {
+            rules: Vec::new(),
+            auto_priority: false,
+            errors: Vec::new(),
+        }
+    }
+}
+
+/// Collects the rules of a [`Tokenizer`]. Created by [`RuleSet::builder`].
+pub struct RuleSetBuilder<N: Integer = u32> {
+    rules: Vec<RuleSpec<N>>,
+    auto_priority: bool,
+    /// Misuses of the builder, that are reported by [`RuleSetBuilder::build`].
+    errors: Vec<RuleError>,
+}
+
+/// A rule, that is constructed in [`RuleSetBuilder::build`].
+enum RuleSpec<N: Integer> {
+    Divisible {
+        divisors: Vec<N>,
+        token: Cow<'static, str>,
+        priority: Option<u32>,
+    },
+    Consecutive {
+        divisor: N,
+        token: Cow<'static, str>,
+        suffix: Cow<'static, str>,
+        rivals: Vec<N>,
+        priority: Option<u32>,
+    },
+    Numeric,
+    Custom(Box<dyn TokenCondition<N>>),
+}
+
+impl<N: Integer> RuleSpec<N> {
+    /// The name of the rule, as reported in a [`RuleError`].
+    fn name(&self) -> String {
+        match self {
+            Self::Divisible { token, .. } | Self::Consecutive { token, .. } => token.to_string(),
+            Self::Numeric => "Numeric".to_string(),
+            Self::Custom(rule) => rule.name().into_owned(),
+        }
+    }
+}
+
+impl<N: Integer> RuleSetBuilder<N> {
+    /// Adds a [`Traditional`] rule, that produces `token` for the multiples of `divisor`.
+    pub fn divisible_by(self, divisor: N, token: impl Into<Cow<'static, str>>) -> Self {
+        self.divisible_by_all([divisor], token)
+    }
+
+    /// Adds a [`Traditional`] rule, that produces `token` for the common
+    /// multiples of all `divisors`.
+    pub fn divisible_by_all(
+        mut self,
+        divisors: impl IntoIterator<Item = N>,
+        token: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        self.rules.push(RuleSpec::Divisible {
+            divisors: divisors.into_iter().collect(),
+            token: token.into(),
+            priority: None,
+        });
+        self
+    }
+
+    /// Adds a [`ConsecutiveTokens`] rule, that produces `token` for the multiples of
+    /// `divisor`, followed by `suffix` for every prior multiple, that was not
+    /// interrupted by a multiple of one of the `rivals`.
+    pub fn consecutive(
+        mut self,
+        divisor: N,
+        token: impl Into<Cow<'static, str>>,
+        suffix: impl Into<Cow<'static, str>>,
+        rivals: impl IntoIterator<Item = N>,
+    ) -> Self {
+        self.rules.push(RuleSpec::Consecutive {
+            divisor,
+            token: token.into(),
+            suffix: suffix.into(),
+            rivals: rivals.into_iter().collect(),
+            priority: None,
+        });
+        self
+    }
+
+    /// Adds the [`Numeric`] rule, which produces the iteration itself, whenever
+    /// no other rule matches.
+    pub fn fallback_numeric(mut self) -> Self {
+        self.rules.push(RuleSpec::Numeric);
+        self
+    }
+
+    /// Adds a rule, that is already constructed. Its priority is kept as is.
+    pub fn rule(mut self, rule: impl TokenCondition<N> + 'static) -> Self {
+        self.rules.push(RuleSpec::Custom(Box::new(rule)));
+        self
+    }
+
+    /// Sets the priority of the rule, that was added last.
+    /// Rules without a priority get a priority of 1, unless [`Self::auto_priority`] is set.
+    ///
+    /// If the last rule was neither added by [`Self::divisible_by`],
+    /// [`Self::divisible_by_all`] nor [`Self::consecutive`], or no rule was added yet,
+    /// [`Self::build`] reports a [`FizzBuzzError::MisplacedPriority`].
+    pub fn priority(mut self, priority: u32) -> Self {
+        let index = self.rules.len().saturating_sub(1);
+        match self.rules.last_mut() {
+            Some(
+                RuleSpec::Divisible { priority: p, .. } | RuleSpec::Consecutive { priority: p, .. },
+            ) => {
+                *p = Some(priority);
+            }
+            last => self.errors.push(RuleError {
+                index,
+                name: last.map(|spec| spec.name()).unwrap_or_default(),
+                error: FizzBuzzError::MisplacedPriority { priority },
+            }),
+        }
+        self
+    }
+
+    /// Derives the priorities of all divisibility rules without an explicit priority
+    /// from the divisors they imply: a rule, whose divisor is a multiple of the divisor
+    /// of another rule, only matches where that rule matches as well and therefore
+    /// gets a higher priority.
+    ///
+    /// Rules that do not imply any other rule get a priority of 1, so that they
+    /// take precedence over the [`Numeric`] fallback. A rule that implies others
+    /// gets a priority one above the highest of them, so `15:FizzBuzz` wins over
+    /// `3:Fizz` and `5:Buzz`. Explicit priorities are kept and count for the
+    /// rules implying them.
+    pub fn auto_priority(mut self) -> Self {
+        self.auto_priority = true;
+        self
+    }
+
+    /// Validates and constructs all rules.
+    ///
+    /// # Errors
+    /// Returns [`FizzBuzzError::InvalidRules`] with the errors of every rule, that
+    /// could not be constructed or whose priority was misplaced, and
+    /// [`FizzBuzzError::EmptyRuleSet`] if no rule was added.
+    pub fn build(self) -> Result<Tokenizer<N>> {
+        if self.rules.is_empty() && self.errors.is_empty() {
+            return Err(FizzBuzzError::EmptyRuleSet);
+        }
+        let priorities = if self.auto_priority {
+            self.implied_priorities()
+        } else {
+            vec![DEFAULT_PRIORITY; self.rules.len()]
+        };
+
+        let mut rules: Vec<Box<dyn TokenCondition<N>>> = Vec::with_capacity(self.rules.len());
+        let mut errors = self.errors;
+        for (index, (spec, implied)) in self.rules.into_iter().zip(priorities).enumerate() {
+            let (rule, token): (Result<Box<dyn TokenCondition<N>>>, _) = match spec {
+                RuleSpec::Divisible {
+                    divisors,
+                    token,
+                    priority,
+                } => {
+                    let priority = priority.unwrap_or(implied);
+                    let rule = Traditional::new(token.clone(), priority, divisors);
+                    (rule.map(|r| Box::new(r) as _), token)
+                }
+                RuleSpec::Consecutive {
+                    divisor,
+                    token,
+                    suffix,
+                    rivals,
+                    priority,
+                } => {
+                    let priority = priority.unwrap_or(implied);
+                    let rule =
+                        ConsecutiveTokens::new(token.clone(), suffix, priority, divisor, rivals);
+                    (rule.map(|r| Box::new(r) as _), token)
+                }
+                RuleSpec::Numeric => (Ok(Box::new(Numeric) as _), Cow::Borrowed("Numeric")),
+                RuleSpec::Custom(rule) => (Ok(rule), Cow::Borrowed("")),
+            };
+            match rule {
+                Ok(rule) => rules.push(rule),
+                Err(error) => errors.push(RuleError {
+                    index,
+                    name: token.into_owned(),
+                    error,
+                }),
+            }
+        }
+
+        if !errors.is_empty() {
+            errors.sort_by_key(|e| e.index);
+            return Err(FizzBuzzError::InvalidRules { errors });
+        }
+        Ok(Tokenizer::new(rules))
+    }
+
+    /// The priorities of the divisibility rules, derived from the chains of divisors
+    /// they imply. See [`Self::auto_priority`].
+    fn implied_priorities(&self) -> Vec<u32> {
+        // The multiples a rule matches at most, as a positive divisor.
+        let divisors: Vec<Option<N>> = self
+            .rules
+            .iter()
+            .map(|spec| match spec {
+                RuleSpec::Divisible { divisors, .. } => {
+                    divisors.iter().try_fold(N::one(), |l, d| {
+                        let d = d.abs();
+                        (!d.is_zero() && !d.is_negative()).then_some(())?;
+                        integer::lcm(&l, &d)
+                    })
+                }
+                RuleSpec::Consecutive { divisor, .. } => {
+                    Some(divisor.abs()).filter(|d| !d.is_zero() && !d.is_negative())
+                }
+                RuleSpec::Numeric | RuleSpec::Custom(_) => None,
+            })
+            .collect();
+
+        // Implied divisors are smaller than the divisors implying them, so processing
+        // the rules by ascending divisor sees every implied rule first.
+        let mut order: Vec<usize> = (0..self.rules.len()).collect();
+        order.sort_by(|a, b| divisors[*a].cmp(&divisors[*b]));
+        let mut priorities: Vec<u32> = self
+            .rules
+            .iter()
+            .map(|spec| match spec {
+                RuleSpec::Divisible { priority, .. } | RuleSpec::Consecutive { priority, .. } => {
+                    priority.unwrap_or(DEFAULT_PRIORITY)
+                }
+                RuleSpec::Numeric | RuleSpec::Custom(_) => DEFAULT_PRIORITY,
+            })
+            .collect();
+        for (n, a) in order.iter().enumerate() {
+            let explicit = matches!(
+                self.rules[*a],
+                RuleSpec::Divisible {
+                    priority: Some(_),
+                    ..
+                } | RuleSpec::Consecutive {
+                    priority: Some(_),
+                    ..
+                }
+            );
+            let Some(divisor) = divisors[*a].as_ref().filter(|_| !explicit) else {
+                continue;
+            };
+            let highest_implied = order[..n]
+                .iter()
+                .filter(|b| {
+                    divisors[**b]
+                        .as_ref()
+                        .is_some_and(|d| d < divisor && divisor.is_multiple_of(d))
+                })
+                .map(|b| priorities[*b])
+                .max();
+            if let Some(highest) = highest_implied {
+                priorities[*a] = highest.saturating_add(1);
+            }
+        }
+        priorities
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RuleSet;
+    use crate::test::space_separated;
+    use crate::{
+        error::{FizzBuzzError, RuleError},
+        Direction, IterationRange, Tokenizer,
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_auto_priority() {
+        let tokenizer: Tokenizer = RuleSet::builder()
+            .divisible_by_all([6, 5], "FizzBuzz")
+            .divisible_by(3, "Fizz")
+            .divisible_by(2, "Buzz")
+            .divisible_by(6, "Fezz")
+            .fallback_numeric()
+            .auto_priority()
+            .build()
+            .unwrap();
+        let priorities: Vec<_> = tokenizer.rules().iter().map(|r| r.get_priority()).collect();
+        assert_eq!(priorities, [3, 1, 1, 2, 0]);
+
+        let output = tokenizer.produce_output(12, space_separated()).unwrap();
+        assert_eq!(output, "1 Buzz Fizz Buzz 5 Fezz 7 Buzz Fizz Buzz 11 Fezz");
+        let range = IterationRange::new(30, 30, true, 1, Direction::Ascending).unwrap();
+        let output = tokenizer.produce_output(range, space_separated()).unwrap();
+        assert_eq!(output, "FizzBuzz");
+
+        // Explicit priorities are kept and rank the rules implying them.
+        let tokenizer: Tokenizer = RuleSet::builder()
+            .divisible_by(3, "Fizz")
+            .priority(5)
+            .divisible_by(15, "FizzBuzz")
+            .consecutive(5, "Buzz", "+", [3])
+            .auto_priority()
+            .build()
+            .unwrap();
+        let priorities: Vec<_> = tokenizer.rules().iter().map(|r| r.get_priority()).collect();
+        assert_eq!(priorities, [5, 6, 1]);
+    }
+
+    #[test]
+    fn test_aggregated_errors() {
+        let err = RuleSet::<i32>::builder()
+            .fallback_numeric()
+            .divisible_by(0, "Fizz")
+            .divisible_by(5, "Buzz")
+            .consecutive(3, "Fezz", "+", [5, 0])
+            .build()
+            .err()
+            .unwrap();
+        let expected = FizzBuzzError::InvalidRules {
+            errors: vec![
+                RuleError {
+                    index: 1,
+                    name: "Fizz".to_string(),
+                    error: FizzBuzzError::ZeroDivisor {
+                        rule: "Fizz".to_string(),
+                        position: 0,
+                    },
+                },
+                RuleError {
+                    index: 3,
+                    name: "Fezz".to_string(),
+                    error: FizzBuzzError::ZeroDivisor {
+                        rule: "Fezz".to_string(),
+                        position: 2,
+                    },
+                },
+            ],
+        };
+        assert_eq!(err, expected);
+        assert!(err
+            .to_string()
+            .contains("; rule 3: The rule `Fezz` has a zero divisor at position 2."));
+
+        let err = RuleSet::<u32>::builder().build().err().unwrap();
+        assert_eq!(err, FizzBuzzError::EmptyRuleSet);
+    }
+
+    #[test]
+    fn test_misplaced_priority() {
+        let err = RuleSet::<u32>::builder()
+            .priority(3)
+            .divisible_by(0, "Fizz")
+            .fallback_numeric()
+            .priority(2)
+            .build()
+            .err()
+            .unwrap();
+        let expected = FizzBuzzError::InvalidRules {
+            errors: vec![
+                RuleError {
+                    index: 0,
+                    name: String::new(),
+                    error: FizzBuzzError::MisplacedPriority { priority: 3 },
+                },
+                RuleError {
+                    index: 0,
+                    name: "Fizz".to_string(),
+                    error: FizzBuzzError::ZeroDivisor {
+                        rule: "Fizz".to_string(),
+                        position: 0,
+                    },
+                },
+                RuleError {
+                    index: 1,
+                    name: "Numeric".to_string(),
+                    error: FizzBuzzError::MisplacedPriority { priority: 2 },
+                },
+            ],
+        };
+        assert_eq!(err, expected);
+
+        // Misplaced priorities are reported, even if no rule was added.
+        let err = RuleSet::<u32>::builder().priority(1).build().err().unwrap();
+        assert!(matches!(err, FizzBuzzError::InvalidRules { .. }));
+    }
+}
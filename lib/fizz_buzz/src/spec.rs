@@ -0,0 +1,377 @@
+//! A compact textual specification of the rules of a [`Tokenizer`].
+//!
+//! The grammar is documented on the [`FromStr`] implementation of [`Tokenizer`].
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{FizzBuzzError, Result};
+use crate::integer::Integer;
+use crate::rule_set::{RuleSet, RuleSetBuilder};
+use crate::tokenizer::Tokenizer;
+
+/// Characters with a meaning in a specification, that have to be escaped in tokens.
+const RESERVED: [char; 7] = [',', ':', '@', '~', '/', '&', '\\'];
+
+/// The keyword of the [`crate::Numeric`] rule.
+pub(crate) const NUMERIC: &str = "numeric";
+
+/// Parses a specification like `3:Fizz,5:Buzz,15:FizzBuzz,numeric`.
+///
+/// A specification is a comma separated list of rules:
+/// - `3:Fizz` produces `Fizz` for the multiples of 3, see [`crate::Traditional`].
+///   Several divisors are joined by `&`, so `3&5:FizzBuzz` produces `FizzBuzz`
+///   for the common multiples of 3 and 5.
+/// - `3:Fizz~+/5&7` produces `Fizz`, followed by a `+` for every prior multiple of 3,
+///   that was not interrupted by a multiple of 5 or 7, see [`crate::ConsecutiveTokens`].
+///   The list of rivals after the `/` is optional.
+/// - `numeric` produces the iteration itself, see [`crate::Numeric`].
+///
+/// Every rule but `numeric` can be given a priority with a trailing `@`, like in
+/// `3:Fizz@2`. Rules without a priority are ranked by the divisors they imply,
+/// see [`RuleSetBuilder::auto_priority`]. The characters `,:@~/&\` and leading or
+/// trailing whitespace of tokens and suffixes are escaped with a backslash.
+impl<N: Integer + FromStr> FromStr for Tokenizer<N> {
+    type Err = FizzBuzzError;
+
+    fn from_str(spec: &str) -> Result<Self> {
+        let mut parser = Parser {
+            chars: spec.chars().collect(),
+            position: 0,
+        };
+        let mut builder = RuleSet::builder().auto_priority();
+        loop {
+            builder = parser.rule(builder)?;
+            parser.skip_whitespace();
+            match parser.next() {
+                None => break,
+                Some(',') => {}
+                Some(_) => return Err(parser.unexpected_previous("`,`")),
+            }
+        }
+        builder.build()
+    }
+}
+
+/// Prints the rules as a specification, that parses into the same rules.
+///
+/// Rules that do not describe themselves with [`crate::TokenCondition::spec`]
+/// are printed as their name in angle brackets, which does not parse.
+/// The [`crate::ResolutionStrategy`] is not part of a specification.
+impl<N: Integer> fmt::Display for Tokenizer<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (n, rule) in self.rules().iter().enumerate() {
+            if n > 0 {
+                f.write_str(",")?;
+            }
+            match rule.spec() {
+                Some(spec) => f.write_str(&spec)?,
+                None => write!(f, "<{}>", rule.name())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Escapes the reserved characters of `text`, so that it parses as a token.
+pub(crate) fn escape(text: &str) -> String {
+    let last = text.chars().count().saturating_sub(1);
+    let mut escaped = String::with_capacity(text.len());
+    for (n, c) in text.chars().enumerate() {
+        if RESERVED.contains(&c) || (c.is_whitespace() && (n == 0 || n == last)) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Joins `values` with `&`.
+pub(crate) fn join<N: Integer>(values: &[N]) -> String {
+    let values: Vec<_> = values.iter().map(ToString::to_string).collect();
+    values.join("&")
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    /// Parses a single rule and adds it to `builder`.
+    fn rule<N: Integer + FromStr>(
+        &mut self,
+        builder: RuleSetBuilder<N>,
+    ) -> Result<RuleSetBuilder<N>> {
+        self.skip_whitespace();
+        if self.keyword(NUMERIC) {
+            return Ok(builder.fallback_numeric());
+        }
+
+        let divisors: Vec<N> = if self.peek() == Some(':') {
+            Vec::new()
+        } else {
+            self.numbers()?
+        };
+        self.expect(':')?;
+        let token = self.text()?;
+        if token.is_empty() {
+            return Err(self.unexpected("a token"));
+        }
+
+        let mut builder = if self.peek() == Some('~') {
+            self.next();
+            let divisor = match divisors[..] {
+                [ref divisor] => divisor.clone(),
+                _ => return Err(self.unexpected_previous("`@` or `,`")),
+            };
+            let suffix = self.text()?;
+            let rivals = if self.peek() == Some('/') {
+                self.next();
+                self.numbers()?
+            } else {
+                Vec::new()
+            };
+            builder.consecutive(divisor, token, suffix, rivals)
+        } else {
+            builder.divisible_by_all(divisors, token)
+        };
+
+        if self.peek() == Some('@') {
+            self.next();
+            self.skip_whitespace();
+            let column = self.column();
+            let digits = self.number_text();
+            if digits.is_empty() {
+                return Err(self.unexpected("a number"));
+            }
+            let priority = digits.parse().map_err(|_| invalid_number(column, digits))?;
+            builder = builder.priority(priority);
+        }
+        Ok(builder)
+    }
+
+    /// A non-empty list of divisors, joined by `&`.
+    fn numbers<N: Integer + FromStr>(&mut self) -> Result<Vec<N>> {
+        let mut numbers = vec![self.divisor()?];
+        while self.peek() == Some('&') {
+            self.next();
+            numbers.push(self.divisor()?);
+        }
+        Ok(numbers)
+    }
+
+    fn divisor<N: Integer + FromStr>(&mut self) -> Result<N> {
+        self.skip_whitespace();
+        let column = self.column();
+        let digits = self.number_text();
+        if digits.is_empty() {
+            return Err(self.unexpected("a number"));
+        }
+        let divisor: N = digits.parse().map_err(|_| invalid_number(column, digits))?;
+        if divisor.is_zero() {
+            return Err(FizzBuzzError::SpecZeroDivisor { column });
+        }
+        self.skip_whitespace();
+        Ok(divisor)
+    }
+
+    /// The characters of a number, that are parsed by [`FromStr`].
+    fn number_text(&mut self) -> String {
+        let mut digits = String::new();
+        while let Some(c) = self.peek() {
+            let sign = digits.is_empty() && (c == '-' || c == '+');
+            if !sign && !c.is_ascii_digit() {
+                break;
+            }
+            digits.push(c);
+            self.next();
+        }
+        digits
+    }
+
+    /// A token or suffix up to the next unescaped reserved character, without
+    /// unescaped leading and trailing whitespace.
+    fn text(&mut self) -> Result<String> {
+        self.skip_whitespace();
+        let mut text = String::new();
+        // The length of `text` without its unescaped trailing whitespace.
+        let mut kept = 0;
+        while let Some(c) = self.peek().filter(|c| !RESERVED[..6].contains(c)) {
+            self.next();
+            if c == '\\' {
+                let escaped = self
+                    .next()
+                    .ok_or_else(|| self.unexpected("an escaped character"))?;
+                text.push(escaped);
+            } else {
+                text.push(c);
+                if c.is_whitespace() {
+                    continue;
+                }
+            }
+            kept = text.len();
+        }
+        text.truncate(kept);
+        Ok(text)
+    }
+
+    /// Consumes `keyword`, if it is followed by the end of the rule.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let end = self.position + keyword.chars().count();
+        let matches = self
+            .chars
+            .get(self.position..end)
+            .is_some_and(|chars| chars.iter().copied().eq(keyword.chars()));
+        let terminated = self
+            .chars
+            .get(end)
+            .is_none_or(|c| *c == ',' || c.is_whitespace());
+        if matches && terminated {
+            self.position = end;
+        }
+        matches && terminated
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        if self.peek() != Some(expected) {
+            return Err(self.unexpected(&format!("`{expected}`")));
+        }
+        self.next();
+        Ok(())
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.next();
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += 1;
+        Some(c)
+    }
+
+    /// The column of the next character, counting from 1.
+    fn column(&self) -> usize {
+        self.position + 1
+    }
+
+    /// An error for the next character.
+    fn unexpected(&self, expected: &str) -> FizzBuzzError {
+        self.unexpected_at(self.position, expected)
+    }
+
+    /// An error for the character, that was consumed last.
+    fn unexpected_previous(&self, expected: &str) -> FizzBuzzError {
+        self.unexpected_at(self.position.saturating_sub(1), expected)
+    }
+
+    fn unexpected_at(&self, position: usize, expected: &str) -> FizzBuzzError {
+        let found = match self.chars.get(position) {
+            Some(c) => format!("`{c}`"),
+            None => "the end of the specification".to_string(),
+        };
+        FizzBuzzError::InvalidSpec {
+            column: position + 1,
+            expected: expected.to_string(),
+            found,
+        }
+    }
+}
+
+fn invalid_number(column: usize, text: String) -> FizzBuzzError {
+    FizzBuzzError::InvalidSpecNumber { column, text }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::space_separated;
+    use crate::{error::FizzBuzzError, Tokenizer};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_parse_spec() {
+        let tokenizer: Tokenizer = "3:Fizz, 5:Buzz, 15:FizzBuzz, numeric".parse().unwrap();
+        let output = tokenizer.produce_output(15, space_separated()).unwrap();
+        assert_eq!(
+            output,
+            "1 2 Fizz 4 Buzz Fizz 7 8 Fizz Buzz 11 Fizz 13 14 FizzBuzz"
+        );
+        assert_eq!(
+            tokenizer.to_string(),
+            "3:Fizz@1,5:Buzz@1,15:FizzBuzz@2,numeric"
+        );
+
+        let tokenizer: Tokenizer<i64> = "numeric,3:Fizz~+/5,5:Buzz~+/3,3&5:FizzBuzz@2"
+            .parse()
+            .unwrap();
+        let output = tokenizer.produce_output(10, space_separated()).unwrap();
+        assert_eq!(output, "1 2 Fizz 4 Buzz Fizz 7 8 Fizz+ Buzz");
+    }
+
+    #[test]
+    fn test_spec_round_trip() {
+        let specs = [
+            "numeric,3:Fizz~+/5&7@1,5:Buzz~+@3,3&5:FizzBuzz@2",
+            r"2:\ Fizz\,Buzz\ @1,:Always@0,-4:Four@1",
+        ];
+        for spec in specs {
+            let tokenizer: Tokenizer<i32> = spec.parse().unwrap();
+            assert_eq!(tokenizer.to_string(), spec);
+            let parsed: Tokenizer<i32> = tokenizer.to_string().parse().unwrap();
+            let output = parsed.produce_output(12, space_separated()).unwrap();
+            assert_eq!(
+                output,
+                tokenizer.produce_output(12, space_separated()).unwrap()
+            );
+        }
+
+        let tokenizer: Tokenizer = r"2:\ Fizz\,Buzz\ ".parse().unwrap();
+        let token = tokenizer.token_at(2, &space_separated()).unwrap();
+        assert_eq!(token.as_deref(), Some(" Fizz,Buzz "));
+    }
+
+    #[test]
+    fn test_spec_errors() {
+        let parse = |spec: &str| spec.parse::<Tokenizer<u8>>().err().unwrap();
+        let invalid = |column, expected: &str, found: &str| FizzBuzzError::InvalidSpec {
+            column,
+            expected: expected.to_string(),
+            found: found.to_string(),
+        };
+
+        assert_eq!(
+            parse(""),
+            invalid(1, "a number", "the end of the specification")
+        );
+        assert_eq!(parse("3:Fizz@1;5:Buzz"), invalid(9, "`,`", "`;`"));
+        assert_eq!(parse("3 Fizz"), invalid(3, "`:`", "`F`"));
+        assert_eq!(
+            parse("3:Fizz,5:"),
+            invalid(10, "a token", "the end of the specification")
+        );
+        assert_eq!(parse("3&5:Fizz~+"), invalid(9, "`@` or `,`", "`~`"));
+        assert_eq!(
+            parse(r"3:Fizz\"),
+            invalid(8, "an escaped character", "the end of the specification")
+        );
+        assert_eq!(
+            parse("3:Fizz,300:Buzz"),
+            FizzBuzzError::InvalidSpecNumber {
+                column: 8,
+                text: "300".to_string()
+            }
+        );
+        assert_eq!(parse("3:Fizz@x"), invalid(8, "a number", "`x`"));
+        assert_eq!(
+            parse("3:Fizz~+/5&0"),
+            FizzBuzzError::SpecZeroDivisor { column: 12 }
+        );
+    }
+}
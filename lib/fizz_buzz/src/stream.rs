@@ -0,0 +1,177 @@
+//! Asynchronous consumption of the tokens of a `FizzBuzz` run.
+//!
+//! A [`TokenStream`] evaluates the rules like [`Tokens`] does, but returns to the
+//! executor after every chunk of iterations, so that long runs do not block other tasks.
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+
+use crate::compiled::CompiledTokenizer;
+use crate::error::Result;
+use crate::formatting::FormattingOptions;
+use crate::integer::Integer;
+use crate::range::IterationRange;
+use crate::tokenizer::{Tokenizer, Tokens};
+
+/// The default number of iterations, that are evaluated between two yields to the executor.
+const CHUNK_LEN: usize = 1024;
+
+/// [`Stream`] of the formatted tokens of a `FizzBuzz` run.
+/// Created by [`Tokenizer::stream`].
+///
+/// After every chunk of evaluated iterations, the stream wakes its task and returns
+/// [`Poll::Pending`] once, so that the executor can run other tasks. This includes
+/// iterations, that do not produce a token, so rules that rarely match do not block.
+pub struct TokenStream<'a, N: Integer = u32> {
+    tokens: Tokens<'a, N>,
+    chunk_len: usize,
+    /// The number of iterations, that may be evaluated before the next yield.
+    budget: usize,
+}
+
+// No field is pinned structurally, the stream only ever accesses them through `&mut Self`.
+impl<N: Integer> Unpin for TokenStream<'_, N> {}
+
+impl<'a, N: Integer> TokenStream<'a, N> {
+    fn new(tokens: Tokens<'a, N>) -> Self {
+        Self {
+            tokens,
+            chunk_len: CHUNK_LEN,
+            budget: CHUNK_LEN,
+        }
+    }
+
+    /// Replaces the number of iterations, that are evaluated between two yields to the executor.
+    pub fn with_chunk_len(mut self, chunk_len: usize) -> Self {
+        self.chunk_len = chunk_len.max(1);
+        self.budget = self.chunk_len;
+        self
+    }
+}
+
+impl<N: Integer> Stream for TokenStream<'_, N> {
+    type Item = Result<String>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        match this.tokens.advance_within(&mut this.budget) {
+            Poll::Ready(advanced) => {
+                Poll::Ready(advanced.map(|r| r.and_then(|()| this.tokens.formatted_token())))
+            }
+            Poll::Pending => {
+                this.budget = this.chunk_len;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<N: Integer> Tokenizer<N> {
+    /// Same as [`Tokenizer::iter`], but returns a [`TokenStream`], that yields to
+    /// the executor after every chunk of evaluated iterations.
+    pub fn stream<'a>(
+        &'a self,
+        range: impl Into<IterationRange<N>>,
+        options: &'a FormattingOptions,
+    ) -> TokenStream<'a, N> {
+        TokenStream::new(self.iter(range, options))
+    }
+}
+
+impl<N: Integer> CompiledTokenizer<N> {
+    /// See [`Tokenizer::stream`].
+    pub fn stream<'a>(
+        &'a self,
+        range: impl Into<IterationRange<N>>,
+        options: &'a FormattingOptions,
+    ) -> TokenStream<'a, N> {
+        TokenStream::new(self.iter(range, options))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{FormattingOptions, Limits, Tokenizer};
+    use futures::executor::block_on;
+    use futures::task::noop_waker_ref;
+    use futures::{Stream, StreamExt, TryStreamExt};
+    use pretty_assertions::assert_eq;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    #[test]
+    fn test_stream() {
+        let tokenizer: Tokenizer = "3:Fizz,5:Buzz,15:FizzBuzz,numeric".parse().unwrap();
+        let options = FormattingOptions::default();
+        let streamed: Vec<String> = block_on(
+            tokenizer
+                .stream(100, &options)
+                .with_chunk_len(7)
+                .try_collect(),
+        )
+        .unwrap();
+        let iterated = tokenizer
+            .iter(100, &options)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(streamed, iterated);
+
+        let compiled = tokenizer.compile();
+        let streamed: Vec<_> = block_on(compiled.stream(100, &options).collect());
+        assert_eq!(streamed.len(), 100);
+    }
+
+    #[test]
+    fn test_stream_yields() {
+        let tokenizer: Tokenizer = "3:Fizz,5:Buzz,numeric".parse().unwrap();
+        let options = FormattingOptions::default();
+        let mut stream = tokenizer.stream(5, &options).with_chunk_len(2);
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut polls = Vec::new();
+        loop {
+            match Pin::new(&mut stream).poll_next(&mut cx) {
+                Poll::Ready(Some(token)) => polls.push(Some(token.unwrap())),
+                Poll::Ready(None) => break,
+                Poll::Pending => polls.push(None),
+            }
+        }
+        let expected = [
+            Some("1"),
+            Some("2"),
+            None,
+            Some("Fizz"),
+            Some("4"),
+            None,
+            Some("Buzz"),
+        ];
+        let expected: Vec<_> = expected.iter().map(|t| t.map(String::from)).collect();
+        assert_eq!(polls, expected);
+    }
+
+    #[test]
+    fn test_stream_yields_without_tokens() {
+        let tokenizer: Tokenizer = "4000000000:Big".parse().unwrap();
+        let options = FormattingOptions::default();
+        let mut stream = tokenizer.stream(10, &options).with_chunk_len(4);
+        let mut cx = Context::from_waker(noop_waker_ref());
+        assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Pending);
+        assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Pending);
+        assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Ready(None));
+    }
+
+    #[test]
+    fn test_stream_stops_after_error() {
+        let tokenizer: Tokenizer = "3:Fizz,5:Buzz,numeric".parse().unwrap();
+        let tokenizer = tokenizer.with_limits(Limits {
+            max_token_len: Some(3),
+            ..Limits::default()
+        });
+        let options = FormattingOptions::default();
+        let streamed: Vec<_> = block_on(tokenizer.stream(7, &options).collect());
+        assert_eq!(streamed.len(), 3);
+        assert!(streamed[2].is_err());
+    }
+}
@@ -1,8 +1,10 @@
 use pretty_assertions::assert_eq;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 use crate::{
-    error::FizzBuzzError, presets::ConsecutiveTokens, FormattingOptions, Numeric, TokenCondition,
-    Tokenizer, Traditional,
+    error::FizzBuzzError, presets::ConsecutiveTokens, EvaluationContext, FormattingOptions,
+    Integer, IterationRange, Numeric, Token, TokenCondition, Tokenizer, Traditional,
 };
 
 /// Function for ergonomic test set up.
@@ -22,17 +24,39 @@ fn create_default_tokenizer(f: u32, b: u32) -> (Tokenizer, FormattingOptions) {
     (tokenizer, options)
 }
 
+/// The traditional `FizzBuzz` rules over the domain `N`, as boxed presets.
+pub(crate) fn fizz_buzz_rules<N: Integer + From<u8>>() -> Vec<Box<dyn TokenCondition<N>>> {
+    vec![
+        Box::new(Numeric),
+        Box::new(Traditional::new("Fizz", 1, vec![N::from(3)]).unwrap()),
+        Box::new(Traditional::new("Buzz", 1, vec![N::from(5)]).unwrap()),
+        Box::new(Traditional::new("FizzBuzz", 2, vec![N::from(3), N::from(5)]).unwrap()),
+    ]
+}
+
+/// Options, that separate the tokens by a space and keep their case.
+pub(crate) fn space_separated() -> FormattingOptions {
+    FormattingOptions {
+        separator: Some(" ".to_string()),
+        case: None,
+    }
+}
+
 #[test]
 fn test_fizz_butt_output() {
     // ------------------------- Passing zero ==> Error ------------------------- //
-    let rule_err = Traditional::new("Fizz", 1, vec![0]).unwrap_err();
-    assert_eq!(FizzBuzzError::NonZeroValue, rule_err);
+    let zero_divisor = |position| FizzBuzzError::ZeroDivisor {
+        rule: "Fizz".to_string(),
+        position,
+    };
+    let rule_err = Traditional::new("Fizz", 1, vec![3, 0]).unwrap_err();
+    assert_eq!(zero_divisor(1), rule_err);
 
     let rule_err = ConsecutiveTokens::new("Fizz", "+", 1, 0, vec![1, 2]).unwrap_err();
-    assert_eq!(FizzBuzzError::NonZeroValue, rule_err);
+    assert_eq!(zero_divisor(0), rule_err);
 
     let rule_err = ConsecutiveTokens::new("Fizz", "+", 1, 4, vec![1, 0]).unwrap_err();
-    assert_eq!(FizzBuzzError::NonZeroValue, rule_err);
+    assert_eq!(zero_divisor(2), rule_err);
     // -------------------------        Example run     ------------------------- //
     let f = 2;
     let b = 7;
@@ -278,3 +302,266 @@ Fizz+
 Fizz++"#;
     assert_eq!(&output, expected);
 }
+
+#[test]
+fn add_stateful_evaluation() {
+    /// Matches at the iteration right after any "Buzz".
+    struct AfterBuzz;
+    impl TokenCondition for AfterBuzz {
+        fn tokenize(&self, _i: u32) -> String {
+            "after".to_string()
+        }
+
+        fn condition(&self, _i: u32) -> bool {
+            false
+        }
+
+        fn evaluate_in(&self, _i: u32, ctx: &mut EvaluationContext<'_>) -> Option<Token<'_>> {
+            let after_buzz = ctx.history().last().and_then(|r| r.token()) == Some("Buzz");
+            after_buzz.then_some(Token::Text("after"))
+        }
+
+        fn get_priority(&self) -> u32 {
+            2
+        }
+
+        fn history_depth(&self) -> usize {
+            1
+        }
+    }
+
+    /// Shouts every third multiple of 3, by counting the multiples in its state.
+    struct ThirdFizz;
+    impl TokenCondition for ThirdFizz {
+        fn tokenize(&self, _i: u32) -> String {
+            "FIZZ!".to_string()
+        }
+
+        fn condition(&self, _i: u32) -> bool {
+            false
+        }
+
+        fn evaluate_in(&self, i: u32, ctx: &mut EvaluationContext<'_>) -> Option<Token<'_>> {
+            let seen = ctx.state::<u32>().copied().unwrap_or_default();
+            let third = i.is_multiple_of(3) && (seen + 1).is_multiple_of(3);
+            third.then_some(Token::Deferred)
+        }
+
+        fn observe(&self, i: u32, ctx: &mut EvaluationContext<'_>) {
+            if i.is_multiple_of(3) {
+                *ctx.state_mut::<u32>() += 1;
+            }
+        }
+
+        fn get_priority(&self) -> u32 {
+            3
+        }
+
+        fn is_stateful(&self) -> bool {
+            true
+        }
+    }
+
+    let options = || FormattingOptions {
+        separator: Some(" ".to_string()),
+        case: None,
+    };
+    let tokenizer = Tokenizer::new(vec![
+        Box::new(Numeric),
+        Box::new(Traditional::new("Fizz", 1, vec![3]).unwrap()),
+        Box::new(Traditional::new("Buzz", 1, vec![5]).unwrap()),
+        Box::new(AfterBuzz),
+        Box::new(ThirdFizz),
+    ]);
+
+    let expected = "1 2 Fizz 4 Buzz after 7 8 FIZZ! Buzz after Fizz 13 14 Buzz";
+    let output = tokenizer.produce_output(15, options()).unwrap();
+    assert_eq!(output, expected);
+
+    // Every run starts with an empty history and fresh state.
+    let output = tokenizer.produce_output(15, options()).unwrap();
+    assert_eq!(output, expected);
+
+    #[cfg(feature = "parallel")]
+    assert_eq!(
+        tokenizer.produce_output_parallel(15, options()).unwrap(),
+        expected
+    );
+}
+
+#[test]
+fn test_observe_without_history() {
+    /// Matches at every second iteration, counted in its state without a history.
+    struct EverySecond(Arc<AtomicUsize>);
+    impl TokenCondition for EverySecond {
+        fn tokenize(&self, _i: u32) -> String {
+            "tick".to_string()
+        }
+
+        fn condition(&self, _i: u32) -> bool {
+            false
+        }
+
+        fn evaluate_in(&self, _i: u32, ctx: &mut EvaluationContext<'_>) -> Option<Token<'_>> {
+            let seen = ctx.state::<u32>().copied().unwrap_or_default();
+            seen.is_multiple_of(2).then_some(Token::Deferred)
+        }
+
+        fn observe(&self, _i: u32, ctx: &mut EvaluationContext<'_>) {
+            *ctx.state_mut::<u32>() += 1;
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn is_stateful(&self) -> bool {
+            true
+        }
+
+        fn get_priority(&self) -> u32 {
+            1
+        }
+    }
+
+    let observed = Arc::new(AtomicUsize::new(0));
+    let rule = EverySecond(Arc::clone(&observed));
+    let tokenizer = Tokenizer::new(vec![Box::new(Numeric), Box::new(rule)]);
+    let options = FormattingOptions {
+        separator: Some(" ".to_string()),
+        case: None,
+    };
+    let output = tokenizer.produce_output(5, options).unwrap();
+    assert_eq!(output, "tick 2 tick 4 tick");
+    assert_eq!(observed.load(Ordering::Relaxed), 5);
+}
+
+#[test]
+fn test_integer_domains() {
+    let options = crate::FormattingOptions {
+        separator: Some(" ".to_string()),
+        case: None,
+    };
+
+    // -------------------------   Signed range crossing zero   ------------------------- //
+    let fall_back = Box::new(Numeric);
+    let fizz = Box::new(ConsecutiveTokens::new("Fizz", "+", 1, 2i64, vec![3]).unwrap());
+    let buzz = Box::new(ConsecutiveTokens::new("Buzz", "+", 1, 3i64, vec![2]).unwrap());
+    let fizz_buzz = Box::new(Traditional::new("FizzBuzz", 2, vec![2i64, 3]).unwrap());
+    let tokenizer = Tokenizer::new(vec![fall_back, fizz, buzz, fizz_buzz]);
+
+    let range = IterationRange::try_from(-3..=3).unwrap();
+    let output = tokenizer.produce_output(range, options.clone()).unwrap();
+    assert_eq!(&output, "Buzz Fizz -1 FizzBuzz 1 Fizz Buzz");
+
+    // The smallest value has no representable absolute value, but is even.
+    let range = IterationRange::try_from(i64::MIN..=i64::MIN + 3).unwrap();
+    let output = tokenizer.produce_output(range, options).unwrap();
+    assert_eq!(
+        &output,
+        "Fizz -9223372036854775807 FizzBuzz -9223372036854775805"
+    );
+
+    // -------------------------  Beyond the range of `u64`  ------------------------- //
+    let options = crate::FormattingOptions {
+        separator: Some(" ".to_string()),
+        case: None,
+    };
+    let offset = 10u128.pow(30);
+    let fall_back = Box::new(Numeric);
+    let fizz = Box::new(Traditional::new("Fizz", 1, vec![2u128]).unwrap());
+    let buzz = Box::new(Traditional::new("Buzz", 1, vec![5u128]).unwrap());
+    let fizz_buzz = Box::new(Traditional::new("FizzBuzz", 2, vec![2u128, 5]).unwrap());
+    let tokenizer = Tokenizer::new(vec![fall_back, fizz, buzz, fizz_buzz]);
+
+    let range = IterationRange::try_from(offset..=offset + 5).unwrap();
+    let output = tokenizer.produce_output(range, options).unwrap();
+    let expected = format!("FizzBuzz {} Fizz {} Fizz Buzz", offset + 1, offset + 3);
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn test_runtime_tokens() {
+    let options = crate::FormattingOptions {
+        separator: Some(" ".to_string()),
+        case: None,
+    };
+
+    // Tokens read at runtime, i.e. from a localisation table, are moved into the rules.
+    let words: Vec<String> = "Fizz Buzz".split(' ').map(|w| format!("{w}!")).collect();
+    let fall_back = Box::new(Numeric);
+    let fizz =
+        Box::new(ConsecutiveTokens::new(words[0].clone(), "?".to_string(), 1, 3, vec![5]).unwrap());
+    let buzz = Box::new(Traditional::new(words[1].clone(), 1, vec![5]).unwrap());
+    let fizz_buzz = Box::new(Traditional::new(words.concat(), 2, vec![3, 5]).unwrap());
+    let tokenizer = Tokenizer::new(vec![fall_back, fizz, buzz, fizz_buzz]);
+    drop(words);
+
+    let output = tokenizer.produce_output(15, options).unwrap();
+    let expected = "1 2 Fizz! 4 Buzz! Fizz! 7 8 Fizz!? Buzz! 11 Fizz! 13 14 Fizz!Buzz!";
+    assert_eq!(output, expected);
+}
+
+#[cfg(feature = "bigint")]
+#[test]
+fn test_big_integers() {
+    use crate::{BigInt, Direction};
+
+    let options = crate::FormattingOptions {
+        separator: Some(" ".to_string()),
+        case: None,
+    };
+    let big = |i: i64| BigInt::from(i);
+
+    let fall_back = Box::new(Numeric);
+    let fizz = Box::new(ConsecutiveTokens::new("Fizz", "+", 1, big(3), vec![big(5)]).unwrap());
+    let buzz = Box::new(ConsecutiveTokens::new("Buzz", "+", 1, big(5), vec![big(3)]).unwrap());
+    let fizz_buzz = Box::new(Traditional::new("FizzBuzz", 2, vec![big(3), big(5)]).unwrap());
+    let tokenizer = Tokenizer::new(vec![fall_back, fizz, buzz, fizz_buzz]);
+
+    // 10^100 is a multiple of 5 and 10^100 - 1 is a multiple of 3.
+    let offset = BigInt::from(10u8).pow(100);
+    let range = IterationRange::new(
+        offset.clone(),
+        offset.clone() + big(14),
+        true,
+        big(2),
+        Direction::Ascending,
+    )
+    .unwrap();
+    let output = tokenizer.produce_output(range, options).unwrap();
+
+    let expected = [
+        "Buzz".to_string(),
+        "Fizz".to_string(),
+        (offset.clone() + big(4)).to_string(),
+        (offset.clone() + big(6)).to_string(),
+        "Fizz".to_string(),
+        "Buzz".to_string(),
+        (offset.clone() + big(12)).to_string(),
+        "Fizz+".to_string(),
+    ]
+    .join(" ");
+    assert_eq!(output, expected);
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_parallel_output() {
+    use crate::Direction;
+
+    let (tokenizer, options) = create_triple_tokenizer(2, 3, 5);
+    let sequential = tokenizer.produce_output(300_000, options).unwrap();
+    let (tokenizer, options) = create_triple_tokenizer(2, 3, 5);
+    let parallel = tokenizer.produce_output_parallel(300_000, options).unwrap();
+    assert!(sequential == parallel);
+
+    // Descending with a step, where not every iteration produces a token.
+    let fizz = Box::new(Traditional::new("Fizz", 1, vec![3]).unwrap());
+    let tokenizer = Tokenizer::new(vec![fizz]);
+    let range = IterationRange::new(1_000_000, 7, false, 5, Direction::Descending).unwrap();
+    let options = || FormattingOptions {
+        separator: Some(",".to_string()),
+        case: None,
+    };
+    let sequential = tokenizer.produce_output(range.clone(), options()).unwrap();
+    let parallel = tokenizer.produce_output_parallel(range, options()).unwrap();
+    assert!(sequential == parallel);
+}
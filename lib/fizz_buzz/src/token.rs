@@ -0,0 +1,73 @@
+use std::fmt;
+
+use crate::integer::Integer;
+
+/// The token of a matching rule, as returned by [`crate::TokenCondition::evaluate`].
+///
+/// A token describes the text of the rule, but is only rendered, if the rule
+/// wins the iteration. Everything a rule computed while matching is carried
+/// along, so rendering does not have to repeat it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token<'a, N: Integer = u32> {
+    /// The rule matched, its text is produced by
+    /// [`crate::TokenCondition::write_token`] if it wins.
+    Deferred,
+    /// A fixed text.
+    Text(&'a str),
+    /// `text`, followed by `count` repetitions of `suffix`.
+    Repeated {
+        text: &'a str,
+        suffix: &'a str,
+        count: usize,
+    },
+    /// The decimal representation of a number.
+    Value(N),
+}
+
+impl<N: Integer> Token<'_, N> {
+    /// Writes the text of the token into `out`.
+    /// [`Token::Deferred`] has no text of its own and writes nothing.
+    pub(crate) fn render(&self, out: &mut impl fmt::Write) -> fmt::Result {
+        match self {
+            Self::Deferred => Ok(()),
+            Self::Text(text) => out.write_str(text),
+            Self::Repeated {
+                text,
+                suffix,
+                count,
+            } => {
+                out.write_str(text)?;
+                for _ in 0..*count {
+                    out.write_str(suffix)?;
+                }
+                Ok(())
+            }
+            Self::Value(value) => write!(out, "{value}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Token;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_render() {
+        let render = |token: Token<'_, i32>| {
+            let mut out = String::new();
+            token.render(&mut out).unwrap();
+            out
+        };
+
+        assert_eq!(render(Token::Deferred), "");
+        assert_eq!(render(Token::Text("Fizz")), "Fizz");
+        assert_eq!(render(Token::Value(-12)), "-12");
+        let repeated = Token::Repeated {
+            text: "Fizz",
+            suffix: "+",
+            count: 3,
+        };
+        assert_eq!(render(repeated), "Fizz+++");
+    }
+}
@@ -1,12 +1,130 @@
-pub trait TokenCondition {
-    // Could be optimized to return a set of instructions
-    // on how to construct the wished token, instead of
-    // allocating a `String` for it.
-    fn tokenize(&self, i: u32) -> String;
+use std::borrow::Cow;
+use std::fmt;
 
-    fn condition(&self, i: u32) -> bool;
+use crate::context::EvaluationContext;
+use crate::integer::Integer;
+use crate::token::Token;
+
+/// Thread safety required from every [`TokenCondition`].
+/// With the `parallel` feature enabled, rules are shared between the threads of a
+/// parallel run and therefore have to be `Send + Sync`. Otherwise there are no requirements.
+#[cfg(feature = "parallel")]
+pub trait ThreadSafety: Send + Sync {}
+#[cfg(feature = "parallel")]
+impl<T: Send + Sync + ?Sized> ThreadSafety for T {}
+
+/// Thread safety required from every [`TokenCondition`].
+/// With the `parallel` feature enabled, rules are shared between the threads of a
+/// parallel run and therefore have to be `Send + Sync`. Otherwise there are no requirements.
+#[cfg(not(feature = "parallel"))]
+pub trait ThreadSafety {}
+#[cfg(not(feature = "parallel"))]
+impl<T: ?Sized> ThreadSafety for T {}
+
+pub trait TokenCondition<N: Integer = u32>: ThreadSafety {
+    /// Produces the token of iteration `i`. Only called for [`Token::Deferred`]
+    /// tokens, see [`TokenCondition::evaluate`] and [`TokenCondition::write_token`].
+    fn tokenize(&self, i: N) -> String;
+
+    fn condition(&self, i: N) -> bool;
 
     fn get_priority(&self) -> u32;
+
+    /// A human readable name of the rule, that is used to refer to it in error messages.
+    /// Defaults to the name of the implementing type.
+    fn name(&self) -> Cow<'_, str> {
+        Cow::Borrowed(std::any::type_name::<Self>())
+    }
+
+    /// Checks the condition of the rule and describes its token in a single pass.
+    /// Returns `None`, if the condition is not met at iteration `i`.
+    /// The returned [`Token`] is only rendered, if the rule wins the iteration.
+    ///
+    /// Defaults to [`TokenCondition::condition`] and a [`Token::Deferred`], that
+    /// is rendered by [`TokenCondition::tokenize`]. Rules whose matching and
+    /// rendering share work should override it.
+    fn evaluate(&self, i: N) -> Option<Token<'_, N>> {
+        self.condition(i).then_some(Token::Deferred)
+    }
+
+    /// Same as [`TokenCondition::evaluate`], but with access to the prior iterations
+    /// of the run and to the state of the rule. This is what the [`crate::Tokenizer`]
+    /// calls. Defaults to [`TokenCondition::evaluate`], ignoring the context.
+    fn evaluate_in(&self, i: N, ctx: &mut EvaluationContext<'_, N>) -> Option<Token<'_, N>> {
+        let _ = ctx;
+        self.evaluate(i)
+    }
+
+    /// Same as [`TokenCondition::tokenize`], but with access to the prior iterations
+    /// of the run and to the state of the rule. Defaults to [`TokenCondition::tokenize`],
+    /// ignoring the context.
+    fn tokenize_in(&self, i: N, ctx: &mut EvaluationContext<'_, N>) -> String {
+        let _ = ctx;
+        self.tokenize(i)
+    }
+
+    /// Writes the token of iteration `i` into `out`. This is how the [`crate::Tokenizer`]
+    /// renders [`Token::Deferred`] tokens of winning rules.
+    /// Defaults to writing the result of [`TokenCondition::tokenize_in`]. Rules with
+    /// deferred tokens can override it to render without allocating.
+    fn write_token(
+        &self,
+        i: N,
+        ctx: &mut EvaluationContext<'_, N>,
+        out: &mut dyn fmt::Write,
+    ) -> fmt::Result {
+        out.write_str(&self.tokenize_in(i, ctx))
+    }
+
+    /// Called for every rule at the end of each iteration, after its outcome was
+    /// recorded. Allows to update the state of the rule, i.e. by counting the
+    /// iterations it won. Does nothing by default.
+    ///
+    /// Rules that keep a state have to return `true` from [`TokenCondition::is_stateful`],
+    /// as parallel runs, [`crate::Tokenizer::compile`] and [`crate::Tokenizer::count`]
+    /// skip iterations or evaluate them out of order otherwise.
+    fn observe(&self, i: N, ctx: &mut EvaluationContext<'_, N>) {
+        let _ = (i, ctx);
+    }
+
+    /// The number of prior iterations, that the rule needs to look up in the
+    /// [`crate::History`] of the run. Defaults to `0`.
+    fn history_depth(&self) -> usize {
+        0
+    }
+
+    /// The period of the condition of the rule. If `Some(p)`, the rule matches at
+    /// `i + p` exactly if it matches at `i`, independent of the context of the run.
+    /// Its token may still depend on `i`, like the one of [`crate::Numeric`].
+    /// Periodic rules can be compiled into a lookup table by [`crate::Tokenizer::compile`].
+    /// Defaults to `None`, for rules that are not periodic.
+    fn period(&self) -> Option<N> {
+        None
+    }
+
+    /// If `Some(d)`, the condition of the rule is met exactly at the multiples of `d`,
+    /// independent of the context of the run. Allows [`crate::Tokenizer::count`] to
+    /// count the tokens of the rule without iterating.
+    /// Defaults to `None`, for rules that can not be described this way.
+    fn divisor(&self) -> Option<N> {
+        None
+    }
+
+    /// Whether the rule depends on the context of a run, that is on its history
+    /// or on its state. Stateful rules are only evaluated correctly within a
+    /// sequential run, so a parallel run falls back to sequential evaluation.
+    /// Defaults to `true`, if the rule requests a history. Rules that keep a state
+    /// through [`EvaluationContext::state_mut`] have to override it.
+    fn is_stateful(&self) -> bool {
+        self.history_depth() > 0
+    }
+
+    /// Describes the rule in the specification format, that is parsed by
+    /// [`crate::Tokenizer::from_str`](std::str::FromStr), or returns `None`, if the
+    /// rule can not be described by it. Defaults to `None`.
+    fn spec(&self) -> Option<String> {
+        None
+    }
 }
 #[cfg(test)]
 mod test {
@@ -35,7 +153,7 @@ mod test {
             }
 
             fn condition(&self, i: u32) -> bool {
-                i % self.divisor == 0
+                i.is_multiple_of(self.divisor)
             }
 
             fn get_priority(&self) -> u32 {
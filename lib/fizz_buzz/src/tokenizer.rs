@@ -1,49 +1,614 @@
-use crate::error::Result;
+use std::fmt;
+use std::io;
+use std::task::Poll;
+use std::time::Instant;
+
+use crate::compiled::Wheel;
+use crate::context::{EvaluationContext, History, RuleState};
+use crate::control::{CancellationToken, Monitor};
+use crate::error::{FizzBuzzError, Result};
 use crate::formatting::FormattingOptions;
+use crate::integer::Integer;
+use crate::limits::{Limit, Limits, Progress};
+use crate::range::{Direction, IterationRange, Values};
+use crate::resolution::{ResolutionStrategy, TieBreak};
+use crate::token::Token;
 use crate::token_condition::TokenCondition;
 
-pub struct Tokenizer {
+pub struct Tokenizer<N: Integer = u32> {
     /// Vector of pointers to trait objects
-    configured_tokens: Vec<Box<dyn TokenCondition>>,
+    configured_tokens: Vec<Box<dyn TokenCondition<N>>>,
+    resolution: ResolutionStrategy<N>,
+    tie_break: TieBreak,
+    /// The number of prior iterations, that are recorded in the [`History`] of a run.
+    history_depth: usize,
+    /// Whether any rule depends on the context of a run.
+    stateful: bool,
+    limits: Limits,
+    monitor: Monitor,
 }
 
-impl Tokenizer {
-    pub fn new(tokens: Vec<Box<dyn TokenCondition>>) -> Self {
+impl<N: Integer> Tokenizer<N> {
+    pub fn new(tokens: Vec<Box<dyn TokenCondition<N>>>) -> Self {
+        let history_depth = tokens.iter().map(|t| t.history_depth()).max();
+        let stateful = tokens.iter().any(|t| t.is_stateful());
         Self {
             configured_tokens: tokens,
+            resolution: ResolutionStrategy::default(),
+            tie_break: TieBreak::default(),
+            history_depth: history_depth.unwrap_or_default(),
+            stateful,
+            limits: Limits::default(),
+            monitor: Monitor::default(),
+        }
+    }
+
+    /// Replaces the [`ResolutionStrategy`], that decides which rules produce
+    /// the token of an iteration, if several rules match.
+    pub fn with_resolution(mut self, resolution: ResolutionStrategy<N>) -> Self {
+        self.resolution = resolution;
+        self
+    }
+
+    /// Replaces the [`TieBreak`] policy, that decides which rule wins, if several
+    /// matching rules share the highest priority.
+    pub fn with_tie_break(mut self, tie_break: TieBreak) -> Self {
+        self.tie_break = tie_break;
+        self
+    }
+
+    /// Replaces the [`Limits`], that bound the resources of producing the output
+    /// of a run. Exceeding a limit stops the run with
+    /// [`FizzBuzzError::LimitExceeded`]. Counting tokens and locating occurrences
+    /// are not limited.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Calls `observer` every `interval` iterations of producing the output of a run,
+    /// with the number of evaluated iterations and the total number of iterations.
+    pub fn with_progress(
+        mut self,
+        interval: u64,
+        observer: impl Fn(u64, u64) + Send + Sync + 'static,
+    ) -> Self {
+        self.monitor.set_observer(interval, Box::new(observer));
+        self
+    }
+
+    /// Checks `token` before every iteration of producing the output of a run and
+    /// stops the run with [`FizzBuzzError::Cancelled`], once it is cancelled.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.monitor.set_cancellation(token);
+        self
+    }
+
+    /// Returns an iterator that lazily plays the game for the iterations of `range`.
+    /// Passing a plain `u32` value `t` plays the iterations `1..=t`.
+    /// Each item is the formatted token of one iteration. Iterations that do not
+    /// produce a token are skipped. The `separator` of the `options` is not applied,
+    /// as joining the tokens is up to the consumer of the iterator.
+    /// The iterator ends after the first error, like a run exceeding its [`Limits`].
+    pub fn iter<'a>(
+        &'a self,
+        range: impl Into<IterationRange<N>>,
+        options: &'a FormattingOptions,
+    ) -> Tokens<'a, N> {
+        let range = range.into();
+        self.tokens(&range, options)
+    }
+
+    pub fn produce_output(
+        &self,
+        range: impl Into<IterationRange<N>>,
+        options: FormattingOptions,
+    ) -> Result<String> {
+        let mut output = String::new();
+        match self.write_to_fmt(range, &options, &mut output) {
+            Ok(()) => Ok(output),
+            Err(err) => Err(err.with_output(output)),
+        }
+    }
+
+    /// Evaluates the rules for the single iteration `i` and returns its formatted token,
+    /// or `None` if the iteration does not produce one. Takes the same time for every
+    /// `i`, as no prior iterations are played. Rules that depend on the history of a run
+    /// see an empty history, as if the run started at `i`.
+    pub fn token_at(&self, i: N, options: &FormattingOptions) -> Result<Option<String>> {
+        let range = IterationRange::new(i.clone(), i, true, N::one(), Direction::Ascending)?;
+        self.iter(range, options).next().transpose()
+    }
+
+    /// Plays the game for the iterations of `range` and streams the formatted tokens,
+    /// joined by the configured separator, into `writer` as they are produced.
+    /// The output is never held in memory as a whole and rendering the tokens
+    /// reuses the same buffers for all iterations. Wrap unbuffered sinks like
+    /// files or sockets into an [`io::BufWriter`] to avoid a system call per token.
+    pub fn write_to(
+        &self,
+        range: impl Into<IterationRange<N>>,
+        options: &FormattingOptions,
+        writer: impl io::Write,
+    ) -> Result<()> {
+        let range = range.into();
+        self.tokens(&range, options).write_io(writer)
+    }
+
+    /// Same as [`Tokenizer::write_to`], but streams into a [`fmt::Write`] sink.
+    pub fn write_to_fmt(
+        &self,
+        range: impl Into<IterationRange<N>>,
+        options: &FormattingOptions,
+        mut writer: impl fmt::Write,
+    ) -> Result<()> {
+        let range = range.into();
+        self.tokens(&range, options).write_joined(&mut writer)?;
+        Ok(())
+    }
+
+    /// The tokens of a run over `range`, that is bound by the [`Limits`] and monitored.
+    fn tokens<'a>(
+        &'a self,
+        range: &IterationRange<N>,
+        options: &'a FormattingOptions,
+    ) -> Tokens<'a, N> {
+        Tokens::new(self, options, range, 0, range.len()).controlled()
+    }
+
+    /// Whether any rule depends on the context of a run, see [`TokenCondition::is_stateful`].
+    pub(crate) fn is_stateful(&self) -> bool {
+        self.stateful
+    }
+
+    pub(crate) fn rules(&self) -> &[Box<dyn TokenCondition<N>>] {
+        &self.configured_tokens
+    }
+
+    /// Whether a run is bound by [`Limits`], reports its progress or can be cancelled.
+    #[cfg(feature = "parallel")]
+    pub(crate) fn is_controlled(&self) -> bool {
+        !self.limits.is_unlimited() || self.monitor.is_active()
+    }
+
+    pub(crate) fn resolution(&self) -> &ResolutionStrategy<N> {
+        &self.resolution
+    }
+
+    /// Writes the indices of the rules, that produce the token of iteration `i`,
+    /// into `winners`, see [`ResolutionStrategy::resolve`].
+    pub(crate) fn resolve(&self, i: &N, matches: &[usize], winners: &mut Vec<usize>) -> Result<()> {
+        self.resolution
+            .resolve(i, &self.configured_tokens, matches, self.tie_break, winners)
+    }
+
+    /// Renders the unformatted token of iteration `i` into the buffer of the `run`,
+    /// from the rules that are chosen by the [`ResolutionStrategy`] among all rules
+    /// whose conditions are met. Returns `false`, if the iteration has no token.
+    /// Every rule is evaluated once, but only the tokens of the winners are rendered.
+    /// If the winners are known in advance from the `wheel`, only the winners are evaluated.
+    /// Afterwards the outcome is recorded in the history of the `run` and every rule observes it.
+    fn tokenize<'a>(
+        &'a self,
+        i: N,
+        run: &mut RunState<'a, N>,
+        wheel: Option<&Wheel<N>>,
+    ) -> Result<bool> {
+        let RunState {
+            tokens,
+            matches,
+            winners,
+            token,
+            history,
+            states,
+        } = run;
+        let rules = &self.configured_tokens;
+
+        tokens.clear();
+        if let Some(resolved) = wheel.and_then(|w| w.lookup(&i)) {
+            winners.clear();
+            winners.extend_from_slice(resolved);
+            tokens.resize_with(rules.len(), || None);
+            for &n in resolved {
+                tokens[n] = rules[n].evaluate_in(
+                    i.clone(),
+                    &mut EvaluationContext::new(history, &mut states[n], n),
+                );
+            }
+        } else {
+            tokens.extend(rules.iter().enumerate().map(|(n, rule)| {
+                rule.evaluate_in(
+                    i.clone(),
+                    &mut EvaluationContext::new(history, &mut states[n], n),
+                )
+            }));
+            matches.clear();
+            matches.extend((0..tokens.len()).filter(|n| tokens[*n].is_some()));
+            self.resolve(&i, matches, winners)?;
+        }
+
+        token.clear();
+        for &n in winners.iter() {
+            let rendered = match &tokens[n] {
+                Some(Token::Deferred) => rules[n].write_token(
+                    i.clone(),
+                    &mut EvaluationContext::new(history, &mut states[n], n),
+                    token,
+                ),
+                Some(evaluated) => evaluated.render(token),
+                None => Ok(()),
+            };
+            rendered.map_err(|_| FizzBuzzError::Render {
+                rule: rules[n].name().into_owned(),
+                iteration: i.to_string(),
+            })?;
+        }
+
+        let produced = !winners.is_empty();
+        if self.history_depth > 0 {
+            history.record(i.clone(), winners, produced.then_some(token.as_str()));
+        }
+        for (n, rule) in rules.iter().enumerate() {
+            rule.observe(
+                i.clone(),
+                &mut EvaluationContext::new(history, &mut states[n], n),
+            );
         }
+        Ok(produced)
     }
+}
+
+/// Lazy iterator over the formatted tokens of a `FizzBuzz` run.
+/// Created by [`Tokenizer::iter`].
+pub struct Tokens<'a, N: Integer = u32> {
+    tokenizer: &'a Tokenizer<N>,
+    options: &'a FormattingOptions,
+    values: Values<N>,
+    /// Position of the last visited value within the range, starting at 1.
+    position: u64,
+    total: u64,
+    run: RunState<'a, N>,
+    wheel: Option<&'a Wheel<N>>,
+    limits: Option<&'a Limits>,
+    monitor: Option<&'a Monitor>,
+    /// The instant, at which the run exceeds its time limit.
+    deadline: Option<Instant>,
+    /// The number of tokens and bytes of formatted output, that were passed on.
+    produced: u64,
+    bytes: u64,
+    /// Buffer for the formatted token of an iteration, that is reused by all iterations.
+    formatted: String,
+    /// Whether the run stopped with an error, after which it produces no more tokens.
+    failed: bool,
+}
+
+/// The number of iterations between two checks of the deadline of a run.
+const DEADLINE_INTERVAL: u64 = 1024;
+
+/// The context of a run, that is carried from one iteration to the next.
+struct RunState<'a, N: Integer> {
+    /// Buffers for the evaluated rules of an iteration, that are reused by all iterations.
+    /// `tokens` holds the outcome of every rule, in the order of the rules.
+    tokens: Vec<Option<Token<'a, N>>>,
+    matches: Vec<usize>,
+    winners: Vec<usize>,
+    /// The unformatted token of the current iteration.
+    token: String,
+    history: History<N>,
+    /// The state of every rule, in the order of the rules.
+    states: Vec<RuleState>,
+}
 
-    pub fn produce_output(&self, t: u32, options: FormattingOptions) -> Result<String> {
-        let mut tokens = Vec::new();
-        for i in 1..=t {
-            let maybe_token = self
+impl<N: Integer> RunState<'_, N> {
+    fn new(tokenizer: &Tokenizer<N>) -> Self {
+        Self {
+            tokens: Vec::new(),
+            matches: Vec::new(),
+            winners: Vec::new(),
+            token: String::new(),
+            history: History::new(tokenizer.history_depth),
+            states: tokenizer
                 .configured_tokens
                 .iter()
-                .filter(|c| c.condition(i))
-                .max_by_key(|c| c.get_priority());
+                .map(|_| RuleState::default())
+                .collect(),
+        }
+    }
+}
 
-            if let Some(token) = maybe_token {
-                let mut token = token.tokenize(i);
-                options.apply_formatting(&mut token, i, t);
-                tokens.push(token);
+impl<'a, N: Integer> Tokens<'a, N> {
+    /// Creates an iterator over the tokens of `range`, which is a part of a
+    /// run with `total` iterations, that starts after the first `offset` iterations.
+    pub(crate) fn new(
+        tokenizer: &'a Tokenizer<N>,
+        options: &'a FormattingOptions,
+        range: &IterationRange<N>,
+        offset: u64,
+        total: u64,
+    ) -> Self {
+        Self {
+            tokenizer,
+            options,
+            values: range.values(),
+            position: offset,
+            total,
+            run: RunState::new(tokenizer),
+            wheel: None,
+            limits: None,
+            monitor: None,
+            deadline: None,
+            produced: 0,
+            bytes: 0,
+            formatted: String::new(),
+            failed: false,
+        }
+    }
+
+    /// Stops the run with an error, once it exceeds the [`Limits`] of the tokenizer
+    /// or is cancelled, and reports its progress. The time limit starts to run now.
+    pub(crate) fn controlled(mut self) -> Self {
+        let Tokenizer {
+            limits, monitor, ..
+        } = self.tokenizer;
+        self.deadline = limits
+            .time_limit
+            .and_then(|limit| Instant::now().checked_add(limit));
+        self.limits = Some(limits);
+        self.monitor = Some(monitor);
+        self
+    }
+
+    /// Looks up the winners of periodic iterations in the `wheel`.
+    pub(crate) fn with_wheel(mut self, wheel: Option<&'a Wheel<N>>) -> Self {
+        self.wheel = wheel;
+        self
+    }
+
+    /// Advances to the next iteration that produces a token and leaves the
+    /// unformatted token in the buffer of the run.
+    pub(crate) fn advance(&mut self) -> Option<Result<()>> {
+        loop {
+            let mut budget = usize::MAX;
+            if let Poll::Ready(advanced) = self.advance_within(&mut budget) {
+                return advanced;
+            }
+        }
+    }
+
+    /// Same as [`Tokens::advance`], but evaluates at most `budget` iterations and
+    /// deducts them from it. Returns [`Poll::Pending`], if the budget ran out
+    /// before an iteration produced a token.
+    pub(crate) fn advance_within(&mut self, budget: &mut usize) -> Poll<Option<Result<()>>> {
+        if self.failed {
+            return Poll::Ready(None);
+        }
+        while *budget > 0 {
+            let Some(i) = self.values.next() else {
+                return Poll::Ready(None);
+            };
+            *budget -= 1;
+            if let Err(err) = self.check_iteration() {
+                return Poll::Ready(Some(self.fail(err)));
+            }
+            self.position += 1;
+            let produced = self.tokenizer.tokenize(i, &mut self.run, self.wheel);
+            if let Some(monitor) = self.monitor {
+                monitor.observe(self.position, self.total);
+            }
+            match produced {
+                Ok(true) => {
+                    let checked = self.check_token();
+                    return Poll::Ready(Some(checked.or_else(|err| self.fail(err))));
+                }
+                Err(err) => return Poll::Ready(Some(self.fail(err))),
+                Ok(false) => {}
             }
             // unlike the original FizzBuzz game, this set up allows for iterations
             // that do not produce any output. If this should be disallowed in the
             // future, an error should be thrown here.
         }
-        Ok(tokens.join(&options.separator.unwrap_or_default()))
+        Poll::Pending
+    }
+
+    /// Formats the token of the current iteration, see [`Tokens::advance`].
+    pub(crate) fn formatted_token(&mut self) -> Result<String> {
+        let mut token = String::with_capacity(self.run.token.len());
+        let formatted = self
+            .options
+            .write_formatted(&self.run.token, &mut token, self.position, self.total)
+            .map_err(|_| self.write_failed())
+            .and_then(|()| self.spend(token.len()));
+        match formatted {
+            Ok(()) => Ok(token),
+            Err(err) => self.fail(err),
+        }
+    }
+
+    /// Advances to the next iteration, to whose token the rule with the index `rule`
+    /// contributed, and returns its value.
+    pub(crate) fn advance_to_win(&mut self, rule: usize) -> Option<Result<N>> {
+        if self.failed {
+            return None;
+        }
+        while let Some(i) = self.values.next() {
+            self.position += 1;
+            match self
+                .tokenizer
+                .tokenize(i.clone(), &mut self.run, self.wheel)
+            {
+                Ok(true) if self.run.winners.contains(&rule) => return Some(Ok(i)),
+                Err(err) => return Some(self.fail(err)),
+                Ok(_) => {}
+            }
+        }
+        None
+    }
+
+    /// Fails, if evaluating another iteration would exceed the limits of the run
+    /// or the run is cancelled.
+    fn check_iteration(&self) -> Result<()> {
+        if self.monitor.is_some_and(Monitor::is_cancelled) {
+            return Err(FizzBuzzError::Cancelled {
+                progress: self.progress(),
+            });
+        }
+        let Some(limits) = self.limits else {
+            return Ok(());
+        };
+        if let Some(max) = limits.max_iterations.filter(|max| self.position >= *max) {
+            return Err(self.exceeded(Limit::Iterations(max)));
+        }
+        match (self.deadline, limits.time_limit) {
+            (Some(deadline), Some(limit))
+                if self.position.is_multiple_of(DEADLINE_INTERVAL)
+                    && Instant::now() >= deadline =>
+            {
+                Err(self.exceeded(Limit::Time(limit)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails, if the token of the current iteration exceeds the limits of the run.
+    fn check_token(&mut self) -> Result<()> {
+        let max = self.limits.and_then(|limits| limits.max_token_len);
+        if let Some(max) = max.filter(|max| self.run.token.len() > *max) {
+            return Err(self.exceeded(Limit::TokenLength(max)));
+        }
+        Ok(())
+    }
+
+    /// Accounts for a token with `bytes` bytes of output, unless they exceed the limits of the run.
+    fn spend(&mut self, bytes: usize) -> Result<()> {
+        let bytes = self.bytes.saturating_add(bytes as u64);
+        let max = self.limits.and_then(|limits| limits.max_output_bytes);
+        if let Some(max) = max.filter(|max| bytes > *max) {
+            return Err(self.exceeded(Limit::OutputBytes(max)));
+        }
+        self.bytes = bytes;
+        self.produced += 1;
+        Ok(())
+    }
+
+    fn exceeded(&self, limit: Limit) -> FizzBuzzError {
+        FizzBuzzError::LimitExceeded {
+            limit,
+            progress: self.progress(),
+        }
+    }
+
+    /// Stops the run with `err`, so that it produces no more tokens.
+    fn fail<T>(&mut self, err: FizzBuzzError) -> Result<T> {
+        self.failed = true;
+        Err(err)
+    }
+
+    fn write_failed(&self) -> FizzBuzzError {
+        FizzBuzzError::Fmt {
+            position: self.position,
+        }
+    }
+
+    fn progress(&self) -> Progress {
+        Progress {
+            iterations: self.position,
+            tokens: self.produced,
+            bytes: self.bytes,
+            output: String::new(),
+        }
+    }
+
+    /// The indices of the rules, that produced the token of the current iteration.
+    pub(crate) fn winners(&self) -> &[usize] {
+        &self.run.winners
+    }
+
+    /// Streams all remaining formatted tokens, joined by the configured separator,
+    /// into `out` without allocating per token. Returns the number of written tokens.
+    pub(crate) fn write_joined(mut self, out: &mut impl fmt::Write) -> Result<usize> {
+        let options = self.options;
+        let separator = options.separator.as_deref().unwrap_or_default();
+        let mut written = 0;
+        while let Some(advanced) = self.advance() {
+            advanced?;
+            let separator = if written > 0 { separator } else { "" };
+            self.formatted.clear();
+            options
+                .write_formatted(
+                    &self.run.token,
+                    &mut self.formatted,
+                    self.position,
+                    self.total,
+                )
+                .map_err(|_| self.write_failed())?;
+            self.spend(separator.len() + self.formatted.len())?;
+            out.write_str(separator)
+                .and_then(|()| out.write_str(&self.formatted))
+                .map_err(|_| self.write_failed())?;
+            written += 1;
+        }
+        Ok(written)
+    }
+
+    /// Same as [`Tokens::write_joined`], but streams into an [`io::Write`] sink,
+    /// which is flushed at the end.
+    pub(crate) fn write_io(self, writer: impl io::Write) -> Result<()> {
+        let mut writer = IoWriter {
+            inner: writer,
+            error: None,
+        };
+        let written = self.write_joined(&mut writer);
+        if let Some(err) = writer.error.take() {
+            return Err(err.into());
+        }
+        written?;
+        writer.inner.flush()?;
+        Ok(())
+    }
+}
+
+impl<N: Integer> Iterator for Tokens<'_, N> {
+    type Item = Result<String>;
+
+    /// Returns `None` after the first error, as the run can not be continued.
+    fn next(&mut self) -> Option<Self::Item> {
+        let advanced = self.advance()?;
+        Some(advanced.and_then(|()| self.formatted_token()))
+    }
+}
+
+/// Adapts an [`io::Write`] sink to [`fmt::Write`], keeping the I/O error,
+/// which [`fmt::Error`] can not carry.
+struct IoWriter<W: io::Write> {
+    inner: W,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> fmt::Write for IoWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
     }
 }
 
 #[cfg(test)]
 mod test {
     use crate::{
+        context::EvaluationContext,
+        error::FizzBuzzError,
         formatting::Case,
-        presets::{Numeric, Traditional},
+        presets::{ConsecutiveTokens, Numeric, Traditional},
+        range::{Direction, IterationRange},
+        resolution::{ConcatenationOrder, ResolutionStrategy, TieBreak},
+        token::Token,
+        token_condition::TokenCondition,
         tokenizer::{FormattingOptions, Tokenizer},
     };
     use pretty_assertions::assert_eq;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
 
     /// This test mainly assures API changes are caught by it.
     /// The integration test in `test.rs` tests the complete
@@ -71,4 +636,301 @@ fizzbuzz"#;
 
         assert_eq!(&output, expected);
     }
+
+    #[test]
+    fn test_iter() {
+        let fizz = Box::new(Traditional::new("Fizz", 1, vec![2]).unwrap());
+        let buzz = Box::new(Traditional::new("Buzz", 1, vec![3]).unwrap());
+        let tokenizer = Tokenizer::new(vec![fizz, buzz]);
+
+        let options = FormattingOptions {
+            separator: None,
+            case: Some(Case::Upper),
+        };
+
+        // Iterations without a matching rule are skipped and only the consumed
+        // tokens are evaluated, even for a practically endless run.
+        let tokens = tokenizer
+            .iter(u32::MAX, &options)
+            .take(5)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(tokens, vec!["FIZZ", "BUZZ", "FIZZ", "BUZZ", "FIZZ"]);
+    }
+
+    #[test]
+    fn test_write_to() {
+        let fizz = Box::new(Traditional::new("Fizz", 1, vec![2]).unwrap());
+        let tokenizer = Tokenizer::new(vec![Box::new(Numeric), fizz]);
+        let options = FormattingOptions {
+            separator: Some(", ".to_string()),
+            case: None,
+        };
+
+        let mut bytes = Vec::new();
+        tokenizer.write_to(5, &options, &mut bytes).unwrap();
+        assert_eq!(String::from_utf8(bytes).unwrap(), "1, Fizz, 3, Fizz, 5");
+
+        let mut output = String::new();
+        tokenizer.write_to_fmt(5, &options, &mut output).unwrap();
+        assert_eq!(output, "1, Fizz, 3, Fizz, 5");
+
+        // I/O errors of the sink are passed on to the caller.
+        let mut full = [0u8; 4];
+        let err = tokenizer.write_to(5, &options, &mut full[..]).unwrap_err();
+        assert!(matches!(
+            err,
+            FizzBuzzError::Io {
+                kind: std::io::ErrorKind::WriteZero,
+                ..
+            }
+        ));
+
+        // Formatting errors name the iteration, whose token could not be written.
+        struct Full(usize);
+        impl std::fmt::Write for Full {
+            fn write_str(&mut self, s: &str) -> std::fmt::Result {
+                self.0 = self.0.checked_sub(s.len()).ok_or(std::fmt::Error)?;
+                Ok(())
+            }
+        }
+        let err = tokenizer.write_to_fmt(5, &options, Full(8)).unwrap_err();
+        assert_eq!(err, FizzBuzzError::Fmt { position: 3 });
+    }
+
+    #[test]
+    fn test_iteration_range() {
+        let fizz = Box::new(Traditional::new("Fizz", 1, vec![3]).unwrap());
+        let tokenizer = Tokenizer::new(vec![Box::new(Numeric), fizz]);
+        let options = FormattingOptions {
+            separator: Some(" ".to_string()),
+            case: None,
+        };
+
+        let range = IterationRange::new(10, 1, true, 2, Direction::Descending).unwrap();
+        let output = tokenizer.produce_output(range, options).unwrap();
+        assert_eq!(output, "10 8 Fizz 4 2");
+    }
+
+    #[test]
+    fn test_resolution_strategies() {
+        let rules = || -> Vec<Box<dyn TokenCondition>> {
+            vec![
+                Box::new(Numeric),
+                Box::new(Traditional::new("Buzz", 1, vec![5]).unwrap()),
+                Box::new(Traditional::new("Fizz", 2, vec![3]).unwrap()),
+            ]
+        };
+        let options = || FormattingOptions {
+            separator: Some(" ".to_string()),
+            case: None,
+        };
+        let range = || IterationRange::try_from(9..=15).unwrap();
+
+        // -------------------------   Highest priority   ------------------------- //
+        let tokenizer = Tokenizer::new(rules());
+        let output = tokenizer.produce_output(range(), options()).unwrap();
+        assert_eq!(output, "Fizz Buzz 11 Fizz 13 14 Fizz");
+
+        // -------------------------     Concatenate      ------------------------- //
+        let tokenizer = Tokenizer::new(rules()).with_resolution(ResolutionStrategy::Concatenate(
+            ConcatenationOrder::Priority,
+        ));
+        let output = tokenizer.produce_output(range(), options()).unwrap();
+        assert_eq!(output, "Fizz Buzz 11 Fizz 13 14 FizzBuzz");
+
+        let tokenizer = Tokenizer::new(rules()).with_resolution(ResolutionStrategy::Concatenate(
+            ConcatenationOrder::Registration,
+        ));
+        let output = tokenizer.produce_output(range(), options()).unwrap();
+        assert_eq!(output, "Fizz Buzz 11 Fizz 13 14 BuzzFizz");
+
+        // -------------------------     First match      ------------------------- //
+        let tokenizer = Tokenizer::new(rules()).with_resolution(ResolutionStrategy::FirstMatch);
+        let output = tokenizer.produce_output(range(), options()).unwrap();
+        assert_eq!(output, "9 10 11 12 13 14 15");
+
+        // -------------------------       Custom         ------------------------- //
+        // Every matching rule except for the fallback, in reverse order of registration.
+        let resolver = |_i: &u32, matches: &[&dyn TokenCondition]| -> Vec<usize> {
+            let skip = usize::from(matches.len() > 1);
+            (skip..matches.len()).rev().collect()
+        };
+        let tokenizer =
+            Tokenizer::new(rules()).with_resolution(ResolutionStrategy::Custom(Box::new(resolver)));
+        let output = tokenizer.produce_output(range(), options()).unwrap();
+        assert_eq!(output, "Fizz Buzz 11 Fizz 13 14 FizzBuzz");
+    }
+
+    #[test]
+    fn test_tie_break() {
+        let rules = || -> Vec<Box<dyn TokenCondition>> {
+            vec![
+                Box::new(Numeric),
+                Box::new(Traditional::new("Fizz", 1, vec![2]).unwrap()),
+                Box::new(Traditional::new("Buzz", 1, vec![3]).unwrap()),
+            ]
+        };
+        let options = FormattingOptions {
+            separator: Some(" ".to_string()),
+            case: None,
+        };
+
+        let tokenizer = Tokenizer::new(rules()).with_tie_break(TieBreak::FirstWins);
+        let output = tokenizer.produce_output(6, options).unwrap();
+        assert_eq!(output, "1 Fizz Buzz Fizz 5 Fizz");
+
+        let options = FormattingOptions {
+            separator: Some(" ".to_string()),
+            case: None,
+        };
+        let tokenizer = Tokenizer::new(rules()).with_tie_break(TieBreak::LastWins);
+        let output = tokenizer.produce_output(6, options).unwrap();
+        assert_eq!(output, "1 Fizz Buzz Fizz 5 Buzz");
+
+        // Ties below the highest matching priority are not ambiguous.
+        let options = FormattingOptions {
+            separator: None,
+            case: None,
+        };
+        let mut with_fizz_buzz = rules();
+        with_fizz_buzz.push(Box::new(
+            Traditional::new("FizzBuzz", 2, vec![2, 3]).unwrap(),
+        ));
+        let tokenizer = Tokenizer::new(with_fizz_buzz).with_tie_break(TieBreak::Error);
+        assert!(tokenizer.produce_output(6, options).is_ok());
+
+        let options = FormattingOptions {
+            separator: None,
+            case: None,
+        };
+        let tokenizer = Tokenizer::new(rules()).with_tie_break(TieBreak::Error);
+        let err = tokenizer.produce_output(10, options).unwrap_err();
+        let expected = FizzBuzzError::AmbiguousRules {
+            first: "Fizz".to_string(),
+            second: "Buzz".to_string(),
+            iteration: "6".to_string(),
+        };
+        assert_eq!(err, expected);
+    }
+
+    #[test]
+    fn test_evaluate() {
+        /// Fallback that counts how often it is evaluated and rendered.
+        struct Counted {
+            evaluated: Arc<AtomicUsize>,
+            rendered: Arc<AtomicUsize>,
+        }
+        impl TokenCondition for Counted {
+            fn tokenize(&self, i: u32) -> String {
+                self.rendered.fetch_add(1, Ordering::Relaxed);
+                format!("<{i}>")
+            }
+
+            fn condition(&self, _i: u32) -> bool {
+                true
+            }
+
+            fn evaluate(&self, _i: u32) -> Option<Token<'_>> {
+                self.evaluated.fetch_add(1, Ordering::Relaxed);
+                Some(Token::Deferred)
+            }
+
+            fn get_priority(&self) -> u32 {
+                0
+            }
+        }
+
+        let evaluated = Arc::new(AtomicUsize::new(0));
+        let rendered = Arc::new(AtomicUsize::new(0));
+        let counted = Box::new(Counted {
+            evaluated: Arc::clone(&evaluated),
+            rendered: Arc::clone(&rendered),
+        });
+        let fizz = Box::new(Traditional::new("Fizz", 1, vec![2]).unwrap());
+        let tokenizer = Tokenizer::new(vec![counted, fizz]);
+        let options = FormattingOptions {
+            separator: Some(" ".to_string()),
+            case: None,
+        };
+
+        // Every rule is evaluated once per iteration, but only winners are rendered.
+        let output = tokenizer.produce_output(6, options).unwrap();
+        assert_eq!(output, "<1> Fizz <3> Fizz <5> Fizz");
+        assert_eq!(evaluated.load(Ordering::Relaxed), 6);
+        assert_eq!(rendered.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn test_write_token() {
+        /// Fallback that renders its token without allocating.
+        struct Hashed;
+        impl TokenCondition for Hashed {
+            fn tokenize(&self, _i: u32) -> String {
+                unreachable!("rendered by `write_token`")
+            }
+
+            fn write_token(
+                &self,
+                i: u32,
+                _ctx: &mut EvaluationContext<'_>,
+                out: &mut dyn std::fmt::Write,
+            ) -> std::fmt::Result {
+                if i > 4 {
+                    return Err(std::fmt::Error);
+                }
+                write!(out, "#{i}")
+            }
+
+            fn condition(&self, _i: u32) -> bool {
+                true
+            }
+
+            fn get_priority(&self) -> u32 {
+                0
+            }
+        }
+
+        let fizz = Box::new(Traditional::new("Fizz", 1, vec![2]).unwrap());
+        let tokenizer = Tokenizer::new(vec![Box::new(Hashed), fizz]);
+        let options = FormattingOptions {
+            separator: Some(" ".to_string()),
+            case: Some(Case::Upper),
+        };
+        let output = tokenizer.produce_output(4, options.clone()).unwrap();
+        assert_eq!(output, "#1 FIZZ #3 FIZZ");
+
+        let err = tokenizer.produce_output(5, options).unwrap_err();
+        let expected = FizzBuzzError::Render {
+            rule: std::any::type_name::<Hashed>().to_string(),
+            iteration: "5".to_string(),
+        };
+        assert_eq!(err, expected);
+    }
+
+    #[test]
+    fn test_token_at() {
+        let fizz = Box::new(ConsecutiveTokens::new("Fizz", "+", 1, 2, vec![5]).unwrap());
+        let buzz = Box::new(Traditional::new("Buzz", 1, vec![5]).unwrap());
+        let tokenizer = Tokenizer::new(vec![Box::new(Numeric), fizz, buzz]);
+        let options = FormattingOptions {
+            separator: None,
+            case: Some(Case::Upper),
+        };
+
+        // Matches the output of a sequential run.
+        let output = tokenizer.iter(30, &options).collect::<Result<Vec<_>, _>>();
+        for (i, expected) in (1..=30).zip(output.unwrap()) {
+            assert_eq!(tokenizer.token_at(i, &options).unwrap(), Some(expected));
+        }
+
+        // 987654318 is the second even number after the last multiple of 5 at 987654315.
+        let token = tokenizer.token_at(987_654_318, &options).unwrap();
+        assert_eq!(token.as_deref(), Some("FIZZ+"));
+
+        let tokenizer = Tokenizer::new(vec![Box::new(
+            Traditional::new("Fizz", 1, vec![3]).unwrap(),
+        )]);
+        assert_eq!(tokenizer.token_at(4, &options).unwrap(), None);
+    }
 }